@@ -0,0 +1,366 @@
+use std::io::{self, BufRead, Write};
+
+use ::time::{Duration, PreciseTime};
+
+use ::consts::WORD_COLUMN_WIDTH;
+use ::game::{self, CursorEntity, GameEnding, GameEvent, GameSettings, GameState, InputEvent,
+             Subscriber};
+use ::i18n;
+use ::stats::StatsTracker;
+use ::words;
+
+/// The commands, which are the same in every language, and the messages that explain them.
+const COMMANDS: [(&'static str, &'static str); 10] = [("words", "help-words"),
+                                                      ("brackets", "help-brackets"),
+                                                      ("guess WORD", "help-guess"),
+                                                      ("bracket NUMBER", "help-bracket"),
+                                                      ("likeness WORD", "help-likeness"),
+                                                      ("next, previous", "help-next"),
+                                                      ("select", "help-select"),
+                                                      ("status", "help-status"),
+                                                      ("log", "help-log"),
+                                                      ("quit", "help-quit")];
+
+/// A word or bracket pair on the board.
+struct Target {
+    /// Words and bracket pairs are numbered separately, in the order they appear on the board.
+    number: usize,
+
+    column_index: usize,
+    line: usize,
+    position: (i32, i32),
+    entity: CursorEntity,
+}
+
+impl Target {
+    fn word(&self) -> Option<&str> {
+        match self.entity {
+            CursorEntity::Word { ref word, .. } => Some(word),
+            CursorEntity::Brackets { .. } => None,
+        }
+    }
+
+    /// Whether the target can still be guessed or used.
+    fn available(&self) -> bool {
+        match self.entity {
+            CursorEntity::Word { guessed, removed, .. } => !guessed && !removed,
+            CursorEntity::Brackets { consumed, .. } => !consumed,
+        }
+    }
+
+    fn describe(&self) -> String {
+        let column = if self.column_index == 0 {
+            i18n::message("say-left-column")
+        } else {
+            i18n::message("say-right-column")
+        };
+        match self.entity {
+            CursorEntity::Word { ref word, .. } => {
+                i18n::message_with("say-word",
+                                   &[&self.number, &words::to_upper(word), &column, &self.line])
+            }
+            CursorEntity::Brackets { pair, .. } => {
+                i18n::message_with("say-brackets",
+                                   &[&self.number,
+                                     &i18n::message(bracket_name(pair.0)),
+                                     &column,
+                                     &self.line])
+            }
+        }
+    }
+}
+
+/// Names brackets in words, since screen readers read them out inconsistently. Returns the key
+/// of the message with the name.
+fn bracket_name(bracket: char) -> &'static str {
+    match bracket {
+        '<' => "say-angle-brackets",
+        '[' => "say-square-brackets",
+        '{' => "say-curly-brackets",
+        _ => "say-round-brackets",
+    }
+}
+
+/// Every word and bracket pair on the board, in reading order: down the left column, then down
+/// the right.
+fn targets(game_state: &GameState) -> Vec<Target> {
+    let mut targets = vec![];
+    let (mut words, mut brackets) = (0, 0);
+    for (column_index, column) in game_state.columns.iter().enumerate() {
+        let mut entities = column.entities().to_vec();
+        entities.sort_by_key(|entity| entity.indices().0);
+
+        for entity in entities {
+            let index = entity.indices().0;
+            let number = match entity {
+                CursorEntity::Word { .. } => {
+                    words += 1;
+                    words
+                }
+                CursorEntity::Brackets { .. } => {
+                    brackets += 1;
+                    brackets
+                }
+            };
+            targets.push(Target {
+                number: number,
+                column_index: column_index,
+                line: index / WORD_COLUMN_WIDTH as usize + 1,
+                position: game::screen_position(column_index, index),
+                entity: entity,
+            });
+        }
+    }
+    targets
+}
+
+fn say(text: &str) {
+    println!("{}", text);
+}
+
+fn say_help() {
+    say(&i18n::message("say-commands"));
+    for &(command, key) in COMMANDS.iter() {
+        say(&format!("  {:<18}{}", command, i18n::message(key)));
+    }
+}
+
+fn say_status(game_state: &GameState) {
+    let mut status = i18n::message_with("say-attempts", &[&game_state.attempts]);
+    if let Some(time_left) = game_state.time_left {
+        let seconds = (time_left.num_milliseconds() + 999) / 1000;
+        status.push(' ');
+        status.push_str(&i18n::message_with("say-seconds", &[&seconds]));
+    }
+    say(&status);
+}
+
+fn say_words(game_state: &GameState) {
+    say(&i18n::message("say-words"));
+    for target in targets(game_state) {
+        match target.entity {
+            CursorEntity::Word { removed: false, guessed, .. } => {
+                if guessed {
+                    say(&format!("  {} {}", target.describe(), i18n::message("say-guessed")));
+                } else {
+                    say(&format!("  {}", target.describe()));
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn say_brackets(game_state: &GameState) {
+    let brackets = targets(game_state)
+        .into_iter()
+        .filter(|target| target.word().is_none() && target.available())
+        .collect::<Vec<_>>();
+    if brackets.is_empty() {
+        say(&i18n::message("say-no-brackets"));
+        return;
+    }
+    say(&i18n::message("say-bracket-pairs"));
+    for target in brackets {
+        say(&format!("  {}", target.describe()));
+    }
+}
+
+/// Plays a game in plain text, reading one command per line and answering in full sentences, so
+/// that it can be played with a screen reader. Returns whether the player wants to play again.
+pub fn play(settings: &GameSettings, tracker: &mut StatsTracker) -> bool {
+    let mut game_state = match GameState::new(settings) {
+        Ok(game_state) => game_state,
+        Err(e) => {
+            say(&e);
+            return false;
+        }
+    };
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    say(&i18n::message("header"));
+    say(&i18n::message("enter-password"));
+    say_status(&game_state);
+    say_words(&game_state);
+    say_brackets(&game_state);
+    say(&i18n::message("say-help"));
+
+    // The target that next and previous move from, as an index into the available targets.
+    let mut focus: Option<usize> = None;
+
+    let mut last_time = PreciseTime::now();
+    while game_state.status.is_none() {
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => return false,
+        };
+
+        // The clock runs while the player thinks, and may have run out before they answered.
+        let elapsed = last_time.to(PreciseTime::now());
+        last_time = PreciseTime::now();
+        update(&mut game_state, tracker, None, elapsed);
+        if game_state.status.is_some() {
+            break;
+        }
+
+        let mut words = line.split_whitespace();
+        let command = words.next().map(|word| word.to_lowercase());
+        let argument = words.next();
+        let available = targets(&game_state)
+            .into_iter()
+            .filter(Target::available)
+            .collect::<Vec<_>>();
+
+        let event = match (command.as_ref().map(|c| &c[..]), argument) {
+            (None, _) => None,
+            (Some("help"), _) => {
+                say_help();
+                None
+            }
+            (Some("words"), _) => {
+                say_words(&game_state);
+                None
+            }
+            (Some("brackets"), _) => {
+                say_brackets(&game_state);
+                None
+            }
+            (Some("status"), _) => {
+                say_status(&game_state);
+                None
+            }
+            (Some("log"), _) => {
+                let log = game_state.log();
+                if log.is_empty() {
+                    say(&i18n::message("say-log-empty"));
+                }
+                for line in log {
+                    say(&line);
+                }
+                None
+            }
+            (Some("quit"), _) => return false,
+            (Some(verb), Some(word)) if verb == "guess" || verb == "likeness" => {
+                let word = words::normalize(word).unwrap_or_else(|| word.to_string());
+                match available.iter().find(|target| target.word() == Some(&word[..])) {
+                    Some(&Target { position: (x, y), .. }) if verb == "guess" => {
+                        Some(InputEvent::SelectAt(x, y))
+                    }
+                    Some(&Target { position: (x, y), .. }) => Some(InputEvent::InspectAt(x, y)),
+                    None => {
+                        say(&i18n::message_with("say-not-on-board", &[&words::to_upper(&word)]));
+                        None
+                    }
+                }
+            }
+            (Some("bracket"), Some(number)) => {
+                let target = number.parse::<usize>().ok().and_then(|number| {
+                    available.iter()
+                        .find(|target| target.word().is_none() && target.number == number)
+                });
+                match target {
+                    Some(&Target { position: (x, y), .. }) => Some(InputEvent::SelectAt(x, y)),
+                    None => {
+                        say(&i18n::message_with("say-no-bracket", &[&number]));
+                        None
+                    }
+                }
+            }
+            (Some(direction), _) if direction == "next" || direction == "previous" => {
+                // There's always at least one word left while the game is being played.
+                let len = available.len();
+                let index = match focus {
+                    Some(index) if direction == "next" => (index + 1) % len,
+                    Some(index) => (index + len - 1) % len,
+                    None if direction == "next" => 0,
+                    None => len - 1,
+                };
+                focus = Some(index);
+                say(&available[index].describe());
+                let (x, y) = available[index].position;
+                Some(InputEvent::MoveTo(x, y))
+            }
+            (Some("select"), _) => {
+                match focus {
+                    Some(_) if game_state.get_entity_at_cursor().is_some() => {
+                        Some(InputEvent::Action)
+                    }
+                    _ => {
+                        say(&i18n::message("say-nothing-selected"));
+                        None
+                    }
+                }
+            }
+            (Some(command), _) => {
+                say(&i18n::message_with("say-unknown-command", &[&command]));
+                None
+            }
+        };
+
+        if let Some(event) = event {
+            let old_targets = targets(&game_state);
+            let old_entries = game_state.entries.len();
+            let old_attempts = game_state.attempts;
+            update(&mut game_state, tracker, Some(event), Duration::zero());
+
+            for entry in &game_state.entries[old_entries..] {
+                for line in entry.log_lines() {
+                    say(&line);
+                }
+            }
+
+            // Say which word a dud removal took off the board.
+            for (old, new) in old_targets.iter().zip(targets(&game_state).iter()) {
+                if let CursorEntity::Word { ref word, removed: true, .. } = new.entity {
+                    if old.available() {
+                        say(&i18n::message_with("say-removed", &[&words::to_upper(word)]));
+                    }
+                }
+            }
+
+            // Whatever was focused may have been used up.
+            if game_state.entries.len() > old_entries {
+                focus = None;
+            }
+            if game_state.attempts != old_attempts && game_state.status.is_none() {
+                say_status(&game_state);
+            }
+        }
+    }
+
+    match game_state.status {
+        Some(GameEnding::Won) => say(&i18n::message("access-granted")),
+        _ => {
+            say(&i18n::message("terminal-locked"));
+            say(&i18n::message("contact-administrator"));
+        }
+    }
+
+    // Any answer starting with the same letter as the word for yes will do, such as y.
+    let (yes, no) = (i18n::message("say-yes"), i18n::message("say-no"));
+    say(&i18n::message_with("say-play-again", &[&yes, &no]));
+    print!("> ");
+    io::stdout().flush().unwrap();
+    match lines.next() {
+        Some(Ok(answer)) => {
+            let first = |text: &str| text.trim().to_lowercase().chars().next();
+            first(&answer).is_some() && first(&answer) == first(&yes)
+        }
+        _ => false,
+    }
+}
+
+/// Applies an input, keeping the statistics up to date.
+fn update(game_state: &mut GameState,
+          tracker: &mut StatsTracker,
+          event: Option<InputEvent>,
+          elapsed: Duration) {
+    tracker.update(elapsed);
+    let events: Vec<GameEvent> = game_state.update(event, elapsed);
+    for event in &events {
+        tracker.notify(event);
+    }
+}
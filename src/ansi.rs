@@ -0,0 +1,87 @@
+//! Converts screens to ANSI escape sequences, for anything that isn't drawn through ncurses.
+
+use ::screen::{Cell, Screen, Style};
+use ::theme::{ColorPair, Theme};
+
+/// Returns the escape sequence that sets the graphics mode for a style.
+pub fn style_sequence(style: Style, theme: &Theme) -> String {
+    let colors = if style.highlight {
+        theme.highlight
+    } else {
+        theme.normal
+    };
+
+    let mut codes = vec!["0".to_string()];
+    if style.dim {
+        codes.push("2".to_string());
+    }
+    codes.extend(color_codes(colors));
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+fn color_codes(colors: ColorPair) -> Vec<String> {
+    let color = |color: i16, base: i16| {
+        if color < 8 {
+            format!("{}", base + color)
+        } else {
+            format!("{};5;{}", base + 8, color)
+        }
+    };
+    vec![color(colors.foreground, 30), color(colors.background, 40)]
+}
+
+/// Draws a whole screen, starting from the top left corner, and leaves the cursor where the
+/// screen says it should be.
+pub fn render_frame(screen: &Screen, theme: &Theme) -> String {
+    let mut output = String::new();
+    for (row, cells) in screen.rows().enumerate() {
+        render_row(&mut output, row, cells, theme);
+    }
+    finish_frame(&mut output, screen);
+    output
+}
+
+/// Draws only the rows of a screen that differ from the previous one, clearing the terminal
+/// first if the screen changed size. Returns nothing if the screens are the same.
+pub fn render_changes(previous: &Screen, screen: &Screen, theme: &Theme) -> String {
+    if previous == screen {
+        return String::new();
+    }
+    if previous.width() != screen.width() || previous.height() != screen.height() {
+        // The terminal is cleared in the background color of normal text.
+        return format!("{}\x1b[2J{}",
+                       style_sequence(Style::default(), theme),
+                       render_frame(screen, theme));
+    }
+
+    let mut output = String::new();
+    for (row, (old, new)) in previous.rows().zip(screen.rows()).enumerate() {
+        if old != new {
+            render_row(&mut output, row, new, theme);
+        }
+    }
+    finish_frame(&mut output, screen);
+    output
+}
+
+fn render_row(output: &mut String, row: usize, cells: &[Cell], theme: &Theme) {
+    output.push_str(&format!("\x1b[{};1H", row + 1));
+
+    let mut current_style = None;
+    for cell in cells {
+        if current_style != Some(cell.style) {
+            output.push_str(&style_sequence(cell.style, theme));
+            current_style = Some(cell.style);
+        }
+        output.push(cell.ch);
+    }
+}
+
+/// Resets the graphics mode and puts the cursor where the screen says it should be.
+fn finish_frame(output: &mut String, screen: &Screen) {
+    output.push_str("\x1b[0m");
+    match screen.cursor {
+        Some((x, y)) => output.push_str(&format!("\x1b[{};{}H\x1b[?25h", y + 1, x + 1)),
+        None => output.push_str("\x1b[?25l"),
+    }
+}
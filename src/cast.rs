@@ -0,0 +1,72 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::io::prelude::*;
+use std::path::Path;
+
+use ::rustc_serialize::json::Json;
+use ::time::{self, PreciseTime};
+
+use ::ansi;
+use ::screen::Screen;
+use ::theme::Theme;
+
+/// Records rendered frames to an [asciicast v2](https://github.com/asciinema/asciinema) file, so
+/// that a session can be watched in any asciicast player.
+pub struct CastRecorder {
+    writer: BufWriter<File>,
+    theme: Theme,
+    start: PreciseTime,
+
+    /// The last frame that was written. Frames that haven't changed are skipped.
+    last_frame: Option<Screen>,
+}
+
+impl CastRecorder {
+    pub fn create(path: &Path, theme: &Theme) -> io::Result<CastRecorder> {
+        Ok(CastRecorder {
+            writer: BufWriter::new(File::create(path)?),
+            theme: theme.clone(),
+            start: PreciseTime::now(),
+            last_frame: None,
+        })
+    }
+
+    pub fn record(&mut self, frame: &Screen) -> io::Result<()> {
+        if self.last_frame.as_ref() == Some(frame) {
+            return Ok(());
+        }
+
+        // The header needs the size of the terminal, so wait for the first frame to write it.
+        if self.last_frame.is_none() {
+            self.write_header(frame)?;
+        }
+
+        let elapsed = self.start.to(PreciseTime::now());
+        let seconds = elapsed.num_microseconds().unwrap_or(0) as f64 / 1_000_000.0;
+
+        // Clear the screen on the first frame, in case the player doesn't start with a blank one.
+        let mut output = String::new();
+        if self.last_frame.is_none() {
+            output.push_str("\x1b[2J");
+        }
+        output.push_str(&ansi::render_frame(frame, &self.theme));
+
+        writeln!(self.writer, "[{:.6}, \"o\", {}]", seconds, Json::String(output))?;
+        self.writer.flush()?;
+
+        self.last_frame = Some(frame.clone());
+        Ok(())
+    }
+
+    fn write_header(&mut self, frame: &Screen) -> io::Result<()> {
+        let term = env::var("TERM").unwrap_or_else(|_| "xterm".to_string());
+        writeln!(self.writer,
+                 "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}, \
+                  \"env\": {{\"TERM\": {}}}}}",
+                 frame.width(),
+                 frame.height(),
+                 time::get_time().sec,
+                 Json::String(term))
+    }
+}
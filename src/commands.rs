@@ -0,0 +1,149 @@
+use ::consts::CONSOLE_WIDTH;
+
+/// How far the player has got through the maintenance-mode exploit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Start,
+
+    /// The terminal has identified itself.
+    Inquired,
+
+    /// The accounts file can be run by anyone.
+    Unprotected,
+
+    /// The terminal has restarted in maintenance mode.
+    Maintenance,
+}
+
+/// What running a command did.
+pub struct CommandOutput {
+    pub lines: Vec<String>,
+
+    /// Whether the command got past the password, unlocking the terminal.
+    pub bypassed: bool,
+}
+
+/// A small interpreter for the commands that Fallout 1 and 2 era terminals accept, layered over
+/// the console. Entering the right commands in order restarts the terminal in maintenance mode,
+/// where the accounts file can be run without a password.
+pub struct Shell {
+    stage: Stage,
+}
+
+impl Shell {
+    pub fn new() -> Shell {
+        Shell { stage: Stage::Start }
+    }
+
+    pub fn run(&mut self, command: &str) -> CommandOutput {
+        let command = command.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+        let (next, lines, bypassed) = match (&command[..], self.stage) {
+            ("SET TERMINAL/INQUIRE", _) => (Stage::Inquired, vec!["RIT-V300"], false),
+            ("SET FILE/PROTECTION=OWNER:RWED ACCOUNTS.F", Stage::Inquired) => {
+                (Stage::Unprotected, vec!["Protection set."], false)
+            }
+            ("SET HALT RESTART/MAINT", Stage::Unprotected) => {
+                (Stage::Maintenance,
+                 vec!["RETROS BIOS", "RBIOS-4.02.08.00", "Uppermem: 64 KB", "Maintenance mode."],
+                 false)
+            }
+            ("RUN DEBUG/ACCOUNTS.F", Stage::Maintenance) => {
+                (Stage::Maintenance, vec!["Password bypassed."], true)
+            }
+            ("SET FILE/PROTECTION=OWNER:RWED ACCOUNTS.F", _) |
+            ("SET HALT RESTART/MAINT", _) |
+            ("RUN DEBUG/ACCOUNTS.F", _) => (self.stage, vec!["%SYSTEM-F-NOPRIV"], false),
+            _ => (self.stage, vec!["%DCL-W-IVVERB"], false),
+        };
+
+        self.stage = next;
+        CommandOutput {
+            lines: lines.into_iter().map(String::from).collect(),
+            bypassed: bypassed,
+        }
+    }
+}
+
+/// Breaks text into lines that fit in the console, prefixing the first with a prompt.
+pub fn console_lines(text: &str) -> Vec<String> {
+    let chars = format!(">{}", text).chars().collect::<Vec<_>>();
+    chars.chunks(CONSOLE_WIDTH as usize).map(|chunk| chunk.iter().cloned().collect()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Shell;
+
+    const EXPLOIT: [&'static str; 4] = ["SET TERMINAL/INQUIRE",
+                                        "SET FILE/PROTECTION=OWNER:RWED ACCOUNTS.F",
+                                        "SET HALT RESTART/MAINT",
+                                        "RUN DEBUG/ACCOUNTS.F"];
+
+    /// Runs commands, returning the first line each printed and whether the last bypassed the
+    /// password.
+    fn run(shell: &mut Shell, commands: &[&str]) -> (Vec<String>, bool) {
+        let mut first_lines = vec![];
+        let mut bypassed = false;
+        for command in commands {
+            let output = shell.run(command);
+            first_lines.push(output.lines[0].clone());
+            bypassed = output.bypassed;
+        }
+        (first_lines, bypassed)
+    }
+
+    #[test]
+    fn the_commands_in_order_bypass_the_password() {
+        let (lines, bypassed) = run(&mut Shell::new(), &EXPLOIT);
+        assert_eq!(lines,
+                   vec!["RIT-V300", "Protection set.", "RETROS BIOS", "Password bypassed."]);
+        assert!(bypassed);
+    }
+
+    #[test]
+    fn commands_are_read_in_any_case_and_spacing() {
+        let commands = ["set terminal/inquire",
+                        "  set file/protection=owner:rwed accounts.f",
+                        "set   halt restart/maint",
+                        "Run Debug/Accounts.F "];
+        assert!(run(&mut Shell::new(), &commands).1);
+    }
+
+    #[test]
+    fn commands_out_of_order_are_refused() {
+        let mut shell = Shell::new();
+        for command in &EXPLOIT[1..] {
+            let (lines, bypassed) = run(&mut shell, &[command]);
+            assert_eq!(lines, vec!["%SYSTEM-F-NOPRIV"]);
+            assert!(!bypassed);
+        }
+
+        // Skipping a step doesn't get any further.
+        let (lines, bypassed) = run(&mut shell, &[EXPLOIT[0], EXPLOIT[2], EXPLOIT[3]]);
+        assert_eq!(lines, vec!["RIT-V300", "%SYSTEM-F-NOPRIV", "%SYSTEM-F-NOPRIV"]);
+        assert!(!bypassed);
+    }
+
+    #[test]
+    fn unknown_commands_leave_the_stage_alone() {
+        let mut shell = Shell::new();
+        let (lines, _) = run(&mut shell, &[EXPLOIT[0], "DIR", EXPLOIT[1], "", EXPLOIT[2]]);
+        assert_eq!(lines[1], "%DCL-W-IVVERB");
+        assert_eq!(lines[3], "%DCL-W-IVVERB");
+        assert!(run(&mut shell, &[EXPLOIT[3]]).1);
+    }
+
+    #[test]
+    fn repeating_commands() {
+        let mut shell = Shell::new();
+
+        // Asking the terminal to identify itself again starts over.
+        let (_, bypassed) = run(&mut shell, &[EXPLOIT[0], EXPLOIT[1], EXPLOIT[0], EXPLOIT[2]]);
+        assert!(!bypassed);
+
+        // Once in maintenance mode, the accounts file can be run again.
+        let (lines, bypassed) = run(&mut shell, &[EXPLOIT[1], EXPLOIT[2], EXPLOIT[3], EXPLOIT[3]]);
+        assert_eq!(lines[3], "Password bypassed.");
+        assert!(bypassed);
+    }
+}
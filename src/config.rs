@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use ::consts::{MAX_DIFFICULTY, MIN_DIFFICULTY};
+use ::keyfile;
+use ::keys::{self, Control, KeyBindings, CONTROLS};
+use ::markers::{self, HighlightStyle, Markers};
+use ::paths;
+
+/// The name of the configuration file in the config directory.
+const CONFIG_FILE: &'static str = "config";
+
+/// Defaults for the command-line options, and the keys used to play.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub difficulty: i32,
+    pub theme: String,
+
+    /// The file passwords are picked from, if not the system dictionary.
+    pub dictionary: Option<String>,
+
+    /// The language of the terminal, if not the one the environment asks for.
+    pub locale: Option<String>,
+
+    pub effects: bool,
+    pub reduced_motion: bool,
+    pub mute: bool,
+    pub sound_command: Option<String>,
+    pub sound_map: Option<String>,
+    pub markers: Markers,
+    pub keys: KeyBindings,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            difficulty: 5,
+            theme: "green".to_string(),
+            dictionary: None,
+            locale: None,
+            effects: false,
+            reduced_motion: false,
+            mute: false,
+            sound_command: None,
+            sound_map: None,
+            markers: Markers::default(),
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+/// Where the configuration file is, if there's a home directory to put it in.
+pub fn path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join(CONFIG_FILE))
+}
+
+impl Config {
+    /// Reads the configuration file, falling back to the defaults if there isn't one.
+    pub fn load() -> Result<Config, String> {
+        match path() {
+            Some(ref path) if path.is_file() => Config::from_file(path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    /// Reads a configuration file. It contains `key = value` lines, such as `difficulty = 7`, and
+    /// `key.CONTROL = KEYS` lines that bind a space-separated list of keys to a control, such as
+    /// `key.up = w up`. Blank lines and lines starting with `#` are ignored.
+    pub fn from_file(path: &Path) -> Result<Config, String> {
+        let mut config = Config::default();
+
+        // The line each key was bound on, to report keys bound to two controls.
+        let mut bound_keys: BTreeMap<i32, (Control, usize)> = BTreeMap::new();
+
+        keyfile::read(path, "key = value", |line_number, key, value| {
+            if key.starts_with("key.") {
+                let name = &key["key.".len()..];
+                let control = Control::from_name(name)
+                    .ok_or_else(|| format!("unknown control '{}'", name))?;
+
+                let mut keys = vec![];
+                for name in value.split_whitespace() {
+                    let key = keys::parse_key(name)
+                        .ok_or_else(|| format!("unknown key '{}'", name))?;
+                    if let Some(&(other, other_line)) = bound_keys.get(&key) {
+                        if other != control {
+                            return Err(format!("'{}' is already bound to {} on line {}",
+                                               name,
+                                               other.name(),
+                                               other_line));
+                        }
+                    }
+                    bound_keys.insert(key, (control, line_number));
+                    keys.push(key);
+                }
+                config.keys.bind(control, &keys);
+                return Ok(());
+            }
+
+            match key {
+                "difficulty" => {
+                    config.difficulty = match value.parse() {
+                        Ok(n) if n >= MIN_DIFFICULTY && n <= MAX_DIFFICULTY => n,
+                        _ => {
+                            return Err(format!("difficulty must be a number from {} to {}",
+                                               MIN_DIFFICULTY,
+                                               MAX_DIFFICULTY))
+                        }
+                    }
+                }
+                "theme" => config.theme = value.to_string(),
+                "locale" => config.locale = Some(value.to_string()),
+                "dictionary" => config.dictionary = Some(value.to_string()),
+                "effects" | "reduced-motion" | "mute" | "inline-likeness" => {
+                    let value = parse_bool(value)
+                        .ok_or_else(|| format!("{} must be true or false", key))?;
+                    match key {
+                        "effects" => config.effects = value,
+                        "reduced-motion" => config.reduced_motion = value,
+                        "mute" => config.mute = value,
+                        _ => config.markers.inline_likeness = value,
+                    }
+                }
+                "highlight" => {
+                    config.markers.highlight = HighlightStyle::parse(value).ok_or_else(|| {
+                        format!("unknown highlight '{}' (expected reverse, underline or bold)",
+                                value)
+                    })?
+                }
+                "guessed-marker" | "removed-marker" => {
+                    let marker = markers::parse_marker(value)
+                        .ok_or_else(|| format!("{} must be a single character", key))?;
+                    if key == "guessed-marker" {
+                        config.markers.guessed = marker;
+                    } else {
+                        config.markers.removed = marker;
+                    }
+                }
+                "sound-command" => config.sound_command = Some(value.to_string()),
+                "sound-map" => config.sound_map = Some(value.to_string()),
+                _ => return Err(format!("unknown key '{}'", key)),
+            }
+            Ok(())
+        })?;
+
+        // Taking a key from a control's defaults could leave it with no keys at all, so that's
+        // only allowed if the file gives the control keys of its own.
+        let defaults = KeyBindings::default();
+        let rebound = bound_keys.values().map(|&(control, _)| control).collect::<BTreeSet<_>>();
+        for (&key, &(control, line_number)) in &bound_keys {
+            match defaults.control(key) {
+                Some(other) if other != control && !rebound.contains(&other) &&
+                               config.keys.control(key) == Some(control) => {
+                    return Err(format!("{}:{}: '{}' is already bound to {} by default (add a \
+                                        key.{} line to bind it to other keys)",
+                                       path.display(),
+                                       line_number,
+                                       keys::key_name(key),
+                                       other.name(),
+                                       other.name()));
+                }
+                _ => (),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Writes the configuration in the format of the configuration file.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "difficulty = {}", self.difficulty)?;
+        writeln!(writer, "theme = {}", self.theme)?;
+        if let Some(ref locale) = self.locale {
+            writeln!(writer, "locale = {}", locale)?;
+        }
+        if let Some(ref dictionary) = self.dictionary {
+            writeln!(writer, "dictionary = {}", dictionary)?;
+        }
+        writeln!(writer, "effects = {}", self.effects)?;
+        writeln!(writer, "reduced-motion = {}", self.reduced_motion)?;
+        writeln!(writer, "mute = {}", self.mute)?;
+        if let Some(ref command) = self.sound_command {
+            writeln!(writer, "sound-command = {}", command)?;
+        }
+        if let Some(ref map) = self.sound_map {
+            writeln!(writer, "sound-map = {}", map)?;
+        }
+        writeln!(writer, "highlight = {}", self.markers.highlight.name())?;
+        writeln!(writer, "guessed-marker = {}", self.markers.guessed)?;
+        writeln!(writer, "removed-marker = {}", self.markers.removed)?;
+        writeln!(writer, "inline-likeness = {}", self.markers.inline_likeness)?;
+
+        writeln!(writer, "")?;
+        for control in CONTROLS.iter() {
+            let keys = self.keys.keys(*control);
+            let names = keys.into_iter().map(keys::key_name).collect::<Vec<_>>();
+            writeln!(writer, "key.{} = {}", control.name(), names.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match &value.to_lowercase()[..] {
+        "true" | "yes" | "on" => Some(true),
+        "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    use ::keys::{self, Control};
+
+    use super::Config;
+
+    /// Reads a configuration file with the given lines. Errors name the file without its
+    /// directory.
+    fn load(name: &str, lines: &[&str]) -> Result<Config, String> {
+        let dir = env::temp_dir().join(format!("robco-term-config-{}", ::std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let mut file = File::create(&path).unwrap();
+        for line in lines {
+            writeln!(file, "{}", line).unwrap();
+        }
+        drop(file);
+
+        let config = Config::from_file(&path);
+        fs::remove_file(&path).unwrap();
+        config.map_err(|e| e.replace(&path.display().to_string(), name))
+    }
+
+    #[test]
+    fn keys_taken_from_other_controls_defaults_are_refused() {
+        let error = load("taken", &["key.prompt = w"]).err().unwrap();
+        assert_eq!(error,
+                   "taken:1: 'w' is already bound to up by default (add a key.up line to bind \
+                    it to other keys)");
+
+        let error = load("twice", &["key.prompt = x", "key.log = x"]).err().unwrap();
+        assert_eq!(error, "twice:2: 'x' is already bound to prompt on line 1");
+    }
+
+    #[test]
+    fn keys_can_move_between_controls_that_are_both_rebound() {
+        // The example in the README swaps k from up to down.
+        let config = load("moved", &["key.up = up i", "key.down = down k"]).unwrap();
+        let k = keys::parse_key("k").unwrap();
+        assert_eq!(config.keys.control(k), Some(Control::Down));
+
+        // The order of the lines doesn't matter.
+        assert!(load("moved", &["key.down = down k", "key.up = up i"]).is_ok());
+    }
+}
@@ -0,0 +1,260 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader};
+use std::io::prelude::*;
+use std::iter::repeat;
+use std::path::{Path, PathBuf};
+
+use ::time;
+
+use ::game::{GameEnding, GameEvent, GameSettings, Subscriber};
+use ::paths;
+
+/// The name of the daily results file in the data directory.
+const DAILY_FILE: &'static str = "daily.tsv";
+
+/// Every daily terminal has passwords of this length.
+pub const DAILY_DIFFICULTY: i32 = 7;
+
+/// The words every daily terminal is built from. They're part of the game rather than read from
+/// the player's dictionary, which differs from one machine to the next.
+const DAILY_WORDS: &'static str = include_str!("../resources/daily_words.txt");
+
+/// Today's date in UTC, so that players in every time zone share the same terminal.
+pub fn today() -> String {
+    time::strftime("%Y-%m-%d", &time::now_utc()).unwrap()
+}
+
+/// The settings for the terminal of a given day. The seed is an FNV-1a hash of the date, which
+/// unlike the standard library's hasher is guaranteed to be the same everywhere.
+pub fn settings(date: &str) -> GameSettings {
+    let mut seed: u64 = 0xcbf29ce484222325;
+    for byte in format!("robco-daily {}", date).bytes() {
+        seed ^= byte as u64;
+        seed = seed.wrapping_mul(0x100000001b3);
+    }
+
+    let words = DAILY_WORDS.lines().map(str::trim).filter(|word| !word.is_empty());
+    GameSettings {
+        seed: seed,
+        word_pool: Some(words.map(String::from).collect()),
+        ..GameSettings::new(DAILY_DIFFICULTY)
+    }
+}
+
+/// The result of one day's terminal.
+#[derive(Debug, Clone)]
+pub struct DailyRecord {
+    pub date: String,
+    pub result: GameEnding,
+    pub word_length: i32,
+
+    /// The likeness of every guess, in order.
+    pub likenesses: Vec<i32>,
+}
+
+impl DailyRecord {
+    /// A spoiler-free summary to share with other players, with a row of blocks for each guess
+    /// showing how many letters were right.
+    pub fn share_lines(&self) -> Vec<String> {
+        let score = match self.result {
+            GameEnding::Won => self.likenesses.len().to_string(),
+            GameEnding::Lost => "X".to_string(),
+        };
+
+        let mut lines = vec![format!("RobCo Daily {}: {}", self.date, score)];
+        for &likeness in &self.likenesses {
+            let misses = self.word_length - likeness;
+            lines.push(repeat('█')
+                .take(likeness as usize)
+                .chain(repeat('░').take(misses as usize))
+                .collect());
+        }
+        lines
+    }
+
+    fn to_line(&self) -> String {
+        let likenesses = self.likenesses.iter().map(|likeness| likeness.to_string());
+        format!("{}\t{}\t{}\t{}",
+                self.date,
+                match self.result {
+                    GameEnding::Won => "won",
+                    GameEnding::Lost => "lost",
+                },
+                self.word_length,
+                likenesses.collect::<Vec<_>>().join(","))
+    }
+
+    fn from_line(line: &str) -> Option<DailyRecord> {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        if fields.len() != 4 {
+            return None;
+        }
+
+        let result = match fields[1] {
+            "won" => GameEnding::Won,
+            "lost" => GameEnding::Lost,
+            _ => return None,
+        };
+
+        let likenesses = if fields[3].is_empty() {
+            Ok(vec![])
+        } else {
+            fields[3].split(',').map(|likeness| likeness.parse()).collect()
+        };
+
+        match (fields[2].parse(), likenesses) {
+            (Ok(word_length), Ok(likenesses)) => {
+                Some(DailyRecord {
+                    date: fields[0].to_string(),
+                    result: result,
+                    word_length: word_length,
+                    likenesses: likenesses,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Watches a daily terminal as it's played and produces a record of it once it ends.
+pub struct DailyTracker {
+    date: String,
+    word_length: i32,
+    likenesses: Vec<i32>,
+    result: Option<GameEnding>,
+}
+
+impl DailyTracker {
+    pub fn new(date: &str, settings: &GameSettings) -> DailyTracker {
+        DailyTracker {
+            date: date.to_string(),
+            word_length: settings.difficulty,
+            likenesses: vec![],
+            result: None,
+        }
+    }
+
+    /// Counts the terminal as lost if it hasn't ended, for a player who leaves it early. The day's
+    /// password can't be played for again once it might have been seen.
+    pub fn abandon(&mut self) {
+        if self.result.is_none() {
+            self.result = Some(GameEnding::Lost);
+        }
+    }
+
+    /// The record of the terminal, if it has been finished.
+    pub fn record(&self) -> Option<DailyRecord> {
+        self.result.map(|result| {
+            DailyRecord {
+                date: self.date.clone(),
+                result: result,
+                word_length: self.word_length,
+                likenesses: self.likenesses.clone(),
+            }
+        })
+    }
+}
+
+impl Subscriber for DailyTracker {
+    fn notify(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::WordGuessed { likeness, .. } => self.likenesses.push(likeness),
+            GameEvent::Won { bypassed: false } => self.result = Some(GameEnding::Won),
+
+            // The day's terminal is used up either way, but only guessing the password solves it.
+            GameEvent::Won { bypassed: true } |
+            GameEvent::LockedOut => self.result = Some(GameEnding::Lost),
+            GameEvent::CursorMoved { .. } |
+            GameEvent::BracketTriggered { .. } => (),
+        }
+    }
+}
+
+/// The results of every daily terminal played, kept in a tab-separated file in the data
+/// directory.
+pub struct DailyStore {
+    path: PathBuf,
+}
+
+impl DailyStore {
+    pub fn open() -> io::Result<DailyStore> {
+        Ok(DailyStore { path: paths::data_file(DAILY_FILE)? })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, record: &DailyRecord) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", record.to_line())
+    }
+
+    /// Finds the result for a day, if its terminal has been played.
+    pub fn find(&self, date: &str) -> io::Result<Option<DailyRecord>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        for (line_number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            match DailyRecord::from_line(&line) {
+                Some(record) => {
+                    if record.date == date {
+                        return Ok(Some(record));
+                    }
+                }
+                None => {
+                    warn!("{}:{}: skipping invalid record",
+                          self.path.display(),
+                          line_number + 1)
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::game::{GameEnding, GameEvent, GameState, Subscriber};
+
+    use super::{settings, DailyTracker, DAILY_DIFFICULTY, DAILY_WORDS};
+
+    #[test]
+    fn the_board_comes_from_the_daily_words() {
+        assert!(DAILY_WORDS.lines().all(|word| word.chars().count() == DAILY_DIFFICULTY as usize));
+
+        let mut settings = settings("2026-10-18");
+        settings.dictionary = Some("/nonexistent".to_string());
+        let game_state = GameState::new(&settings).unwrap();
+        assert!(DAILY_WORDS.lines().any(|word| word == game_state.correct_word));
+
+        let again = GameState::new(&super::settings("2026-10-18")).unwrap();
+        assert_eq!(again.correct_word, game_state.correct_word);
+    }
+
+    #[test]
+    fn leaving_early_loses_the_day() {
+        let settings = settings("2026-10-18");
+        let mut tracker = DailyTracker::new("2026-10-18", &settings);
+        tracker.notify(&GameEvent::WordGuessed {
+            word: "ability".to_string(),
+            likeness: 2,
+            correct: false,
+        });
+        tracker.abandon();
+
+        let record = tracker.record().unwrap();
+        assert_eq!(record.result, GameEnding::Lost);
+        assert_eq!(record.likenesses, vec![2]);
+
+        // A terminal that was finished keeps its result.
+        let mut tracker = DailyTracker::new("2026-10-18", &settings);
+        tracker.notify(&GameEvent::Won { bypassed: false });
+        tracker.abandon();
+        assert_eq!(tracker.record().unwrap().result, GameEnding::Won);
+    }
+}
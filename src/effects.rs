@@ -0,0 +1,135 @@
+use ::time::Duration;
+
+use ::game::{GameEvent, Subscriber};
+use ::screen::Screen;
+
+/// How long the scanlines stay on one set of rows before moving to the other.
+const SCANLINE_INTERVAL_MS: i64 = 500;
+
+/// How long the cursor stays on (or off) while blinking.
+const CURSOR_BLINK_MS: i64 = 530;
+
+/// How long the screen flickers after a denied guess.
+const FLICKER_MS: i64 = 250;
+
+/// How quickly the screen alternates between dim and bright while flickering.
+const FLICKER_PERIOD_MS: i64 = 50;
+
+/// How long the screen rolls when the terminal locks.
+const ROLL_MS: i64 = 600;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EffectSettings {
+    /// Whether any effects should be drawn at all.
+    pub enabled: bool,
+
+    /// Skips everything that moves or blinks, leaving only static scanlines.
+    pub reduced_motion: bool,
+}
+
+/// A layer of CRT-style effects that is applied to each frame after it has been drawn.
+pub struct Effects {
+    settings: EffectSettings,
+
+    /// Total time since the effects were created.
+    clock: Duration,
+
+    /// Time left on the current flicker, if there is one.
+    flicker: Option<Duration>,
+
+    /// Time left on the current roll, if there is one.
+    roll: Option<Duration>,
+}
+
+impl Effects {
+    pub fn new(settings: EffectSettings) -> Effects {
+        Effects {
+            settings: settings,
+            clock: Duration::zero(),
+            flicker: None,
+            roll: None,
+        }
+    }
+
+    fn animated(&self) -> bool {
+        self.settings.enabled && !self.settings.reduced_motion
+    }
+
+    /// Advances the effect timers.
+    pub fn update(&mut self, elapsed: Duration) {
+        self.clock = self.clock + elapsed;
+        self.flicker = tick(self.flicker, elapsed);
+        self.roll = tick(self.roll, elapsed);
+    }
+
+    /// Whether the cursor should be shown in the current frame.
+    pub fn cursor_visible(&self) -> bool {
+        !self.animated() || (self.clock.num_milliseconds() / CURSOR_BLINK_MS) % 2 == 0
+    }
+
+    pub fn apply(&self, screen: &mut Screen) {
+        if !self.settings.enabled {
+            return;
+        }
+
+        // Dim every other row. Unless motion is reduced, the dimmed rows alternate over time like
+        // an interlaced display.
+        let parity = if self.settings.reduced_motion {
+            0
+        } else {
+            (self.clock.num_milliseconds() / SCANLINE_INTERVAL_MS) % 2
+        };
+        for row in 0..screen.height() {
+            if row as i64 % 2 == parity {
+                for cell in screen.row_mut(row) {
+                    cell.style.dim = true;
+                }
+            }
+        }
+
+        if let Some(remaining) = self.flicker {
+            if (remaining.num_milliseconds() / FLICKER_PERIOD_MS) % 2 == 0 {
+                for row in 0..screen.height() {
+                    for cell in screen.row_mut(row) {
+                        cell.style.dim = true;
+                    }
+                }
+            }
+        }
+
+        if let Some(remaining) = self.roll {
+            // Roll the whole screen through once over the course of the effect.
+            let progress = ROLL_MS - remaining.num_milliseconds();
+            let offset = screen.height() as i64 * progress / ROLL_MS;
+            screen.roll(offset as i32);
+        }
+
+        if !self.cursor_visible() {
+            screen.cursor = None;
+        }
+    }
+}
+
+impl Subscriber for Effects {
+    fn notify(&mut self, event: &GameEvent) {
+        if !self.animated() {
+            return;
+        }
+
+        match *event {
+            GameEvent::WordGuessed { correct: false, .. } => {
+                self.flicker = Some(Duration::milliseconds(FLICKER_MS));
+            }
+            GameEvent::LockedOut => self.roll = Some(Duration::milliseconds(ROLL_MS)),
+            _ => (),
+        }
+    }
+}
+
+/// Counts down a timer, discarding it once it has run out.
+fn tick(timer: Option<Duration>, elapsed: Duration) -> Option<Duration> {
+    match timer {
+        Some(remaining) if remaining > elapsed => Some(remaining - elapsed),
+        _ => None,
+    }
+}
@@ -1,14 +1,19 @@
-use std::ascii::AsciiExt;
-use std::fs::File;
-use std::io::BufReader;
-use std::io::prelude::*;
+use std::cmp;
+use std::mem;
+use std::path::Path;
 
 use ::itertools::Itertools;
-use ::rand::{self, Rng};
+use ::rand::{self, Rng, SeedableRng, StdRng};
 use ::rand::distributions::{IndependentSample, Range};
 use ::time::Duration;
 
+use ::commands::{self, Shell};
 use ::consts::*;
+use ::i18n;
+use ::words;
+
+/// The longest command that can be typed at the console.
+const MAX_COMMAND_LENGTH: usize = 64;
 
 pub enum InputEvent {
     Up,
@@ -17,6 +22,68 @@ pub enum InputEvent {
     Right,
     Action,
     Quit,
+    ToggleMute,
+    Screenshot,
+
+    /// Moves the cursor directly to a screen position, e.g. when the mouse moves.
+    MoveTo(i32, i32),
+
+    /// Moves the cursor to a screen position and selects whatever is there, e.g. on a click.
+    SelectAt(i32, i32),
+
+    /// Moves the cursor to a screen position and compares the word there with the words already
+    /// guessed, e.g. on a right click.
+    InspectAt(i32, i32),
+
+    /// Scrolls the console history by a number of rows, back towards older entries if positive.
+    ScrollConsole(i32),
+
+    /// Shows every console entry in full.
+    ShowLog,
+
+    /// Opens the command prompt in the console.
+    CommandPrompt,
+
+    /// Types a character at the command prompt.
+    Type(char),
+
+    /// Deletes the last character typed at the command prompt.
+    Erase,
+}
+
+/// What triggering a bracket pair did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BracketReward {
+    DudRemoved,
+    AllowanceReplenished,
+}
+
+/// Something that happened during an update. Subscribers can react to these instead of comparing
+/// the game state between frames.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    CursorMoved {
+        position: (i32, i32),
+    },
+    WordGuessed {
+        word: String,
+        likeness: i32,
+        correct: bool,
+    },
+    BracketTriggered {
+        reward: BracketReward,
+    },
+    Won {
+        /// Whether the password was got around with the maintenance commands instead of guessed,
+        /// which doesn't count as a solve.
+        bypassed: bool,
+    },
+    LockedOut,
+}
+
+/// Anything that wants to be told about game events.
+pub trait Subscriber {
+    fn notify(&mut self, event: &GameEvent);
 }
 
 pub enum Entry {
@@ -29,16 +96,80 @@ pub enum Entry {
     },
     DudRemoval,
     AllowanceReplenish,
+
+    /// A command typed at the prompt and its output, already broken into console lines.
+    Command {
+        lines: Vec<String>,
+    },
+
+    /// The likeness of a word to each word already guessed, already broken into console lines.
+    Likeness {
+        lines: Vec<String>,
+    },
 }
 
 impl Entry {
+    /// The lines the entry takes up in the console, with messages wrapped to fit.
+    pub fn console_lines(&self) -> Vec<String> {
+        use self::Entry::*;
+        match *self {
+            Correct { ref word } => {
+                let mut lines = vec![format!(">{}", words::to_upper(word))];
+                lines.extend(message_lines(&i18n::message("exact-match")));
+                lines.extend(message_lines(&i18n::message("please-wait")));
+                lines
+            }
+            Incorrect { ref word, num_correct } => {
+                let mut lines = vec![format!(">{}", words::to_upper(word))];
+                lines.extend(message_lines(&i18n::message("entry-denied")));
+                lines.extend(message_lines(&i18n::message_with("likeness-score",
+                                                               &[&num_correct,
+                                                                 &words::length(word)])));
+                lines
+            }
+            DudRemoval => {
+                let mut lines = vec![">".to_string()];
+                lines.extend(message_lines(&i18n::message("dud-removed")));
+                lines
+            }
+            AllowanceReplenish => {
+                let mut lines = vec![">".to_string()];
+                lines.extend(message_lines(&i18n::message("allowance-replenished")));
+                lines
+            }
+            Command { ref lines } | Likeness { ref lines } => lines.clone(),
+        }
+    }
+
     pub fn display_rows(&self) -> usize {
+        self.console_lines().len()
+    }
+
+    /// The entry written out in full, rather than squeezed into the console.
+    pub fn log_lines(&self) -> Vec<String> {
         use self::Entry::*;
         match *self {
-            Correct { .. } => 5,
-            Incorrect { .. } => 3,
-            DudRemoval => 2,
-            AllowanceReplenish => 3,
+            Correct { ref word } => {
+                vec![i18n::message_with("log-exact", &[&words::to_upper(word)])]
+            }
+            Incorrect { ref word, num_correct } => {
+                vec![i18n::message_with("log-denied", &[&words::to_upper(word), &num_correct])]
+            }
+            DudRemoval => vec![i18n::message("dud-removed")],
+            AllowanceReplenish => vec![i18n::message("allowance-replenished")],
+            Command { ref lines } | Likeness { ref lines } => {
+                // Put back together the lines that were broken up to fit in the console.
+                let mut log: Vec<String> = vec![];
+                for line in lines {
+                    if line.starts_with('>') || log.is_empty() {
+                        log.push(line.trim_left_matches('>').to_string());
+                    } else {
+                        let last = log.len() - 1;
+                        log[last].push_str(line);
+                    }
+                }
+                log
+            }
         }
     }
 }
@@ -61,7 +192,7 @@ pub enum CursorEntity {
 impl CursorEntity {
     pub fn indices(&self) -> (usize, usize) {
         match *self {
-            CursorEntity::Word { ref word, index, .. } => (index, index + word.len()),
+            CursorEntity::Word { ref word, index, .. } => (index, index + words::length(word)),
             CursorEntity::Brackets { indices, .. } => (indices.0, indices.1 + 1),
         }
     }
@@ -74,11 +205,58 @@ impl CursorEntity {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameEnding {
     Won,
     Lost,
 }
 
+/// Everything needed to recreate a game. Two games with the same settings have the same board
+/// and the same bracket rewards, as long as they use the same dictionary.
+#[derive(Debug, Clone)]
+pub struct GameSettings {
+    pub difficulty: i32,
+    pub seed: u64,
+
+    /// How many attempts the player starts with.
+    pub attempts: i32,
+
+    /// How many candidate passwords are on the board, split between the columns.
+    pub num_words: i32,
+
+    /// How many bracket pairs each column has.
+    pub brackets: i32,
+
+    /// The words that passwords are picked from, if not the dictionary.
+    pub word_pool: Option<Vec<String>>,
+
+    /// The dictionary that passwords are picked from, if not the system dictionary.
+    pub dictionary: Option<String>,
+
+    /// How long the player has before the terminal locks, if the game is timed.
+    pub time_limit: Option<Duration>,
+}
+
+impl GameSettings {
+    /// Creates settings for an untimed game with a random seed.
+    pub fn new(difficulty: i32) -> GameSettings {
+        GameSettings {
+            difficulty: difficulty,
+            seed: rand::thread_rng().gen(),
+            attempts: STARTING_ATTEMPTS,
+            num_words: NUM_WORDS,
+            brackets: BRACKETS_PER_COLUMN,
+            word_pool: None,
+            dictionary: None,
+            time_limit: None,
+        }
+    }
+
+    fn rng(&self) -> StdRng {
+        StdRng::from_seed(&[(self.seed >> 32) as usize, (self.seed & 0xFFFFFFFF) as usize])
+    }
+}
+
 pub struct GameState {
     pub attempts: i32,
     pub columns: [Column; COLUMNS as usize],
@@ -87,23 +265,147 @@ pub struct GameState {
     pub correct_word: String,
     pub entries: Vec<Entry>,
     pub status: Option<GameEnding>,
+
+    /// Whether the game was won by getting around the password rather than guessing it.
+    pub bypassed: bool,
+
+    /// How long is left before the terminal locks, if the game is timed.
+    pub time_left: Option<Duration>,
+
+    /// The command being typed, if the command prompt is open.
+    pub command_line: Option<String>,
+
+    /// How many rows the console history is scrolled back from the latest entry.
+    pub console_scroll: usize,
+
+    shell: Shell,
+
+    /// Events that have happened during the current update.
+    events: Vec<GameEvent>,
+
+    /// All randomness after the board is generated comes from here, so that games can be replayed.
+    rng: StdRng,
 }
 
 impl GameState {
-    pub fn update(&mut self, event: Option<InputEvent>, elapsed_time: Duration) {
+    /// Applies an input to the game, returning everything that happened as a result.
+    pub fn update(&mut self, event: Option<InputEvent>, elapsed_time: Duration) -> Vec<GameEvent> {
+        let old_position = self.cursor_position;
+        let old_entries = self.entries.len();
+
         if let Some(event) = event {
             match event {
                 InputEvent::Left => self.cursor_position.0 -= 1,
                 InputEvent::Right => self.cursor_position.0 += 1,
                 InputEvent::Up => self.cursor_position.1 -= 1,
                 InputEvent::Down => self.cursor_position.1 += 1,
-                InputEvent::Quit => self.is_playing = false,
-                InputEvent::Action => self.select_entity(),
+                InputEvent::Quit => {
+                    if self.command_line.is_some() {
+                        self.command_line = None;
+                    } else {
+                        self.is_playing = false;
+                    }
+                }
+                InputEvent::Action => {
+                    match self.command_line.take() {
+                        Some(command) => self.run_command(&command),
+                        None => self.select_entity(),
+                    }
+                }
+                InputEvent::ToggleMute | InputEvent::Screenshot | InputEvent::ShowLog => (),
+                InputEvent::MoveTo(x, y) => self.cursor_position = (x, y),
+                InputEvent::SelectAt(x, y) => {
+                    self.cursor_position = (x, y);
+                    self.select_entity();
+                }
+                InputEvent::InspectAt(x, y) => {
+                    self.cursor_position = (x, y);
+                    if self.status.is_none() {
+                        self.inspect_entity();
+                    }
+                }
+                InputEvent::ScrollConsole(rows) => {
+                    let scroll = self.console_scroll as i32 + rows;
+                    let max_scroll = self.max_console_scroll() as i32;
+                    self.console_scroll = cmp::max(0, cmp::min(scroll, max_scroll)) as usize;
+                }
+                InputEvent::CommandPrompt => {
+                    if self.status.is_none() {
+                        self.command_line = Some(String::new());
+                    }
+                }
+                InputEvent::Type(c) => {
+                    if let Some(ref mut line) = self.command_line {
+                        if line.chars().count() < MAX_COMMAND_LENGTH {
+                            line.push(c);
+                        }
+                    }
+                }
+                InputEvent::Erase => {
+                    if let Some(ref mut line) = self.command_line {
+                        line.pop();
+                    }
+                }
             }
         }
 
-        // TODO: Animations
-        let _ = elapsed_time;
+        // Anything new in the console should be seen.
+        if self.entries.len() != old_entries {
+            self.console_scroll = 0;
+        }
+
+        if self.cursor_position != old_position {
+            // The cursor move happened before anything it selected, so it goes first.
+            self.events.insert(0, GameEvent::CursorMoved { position: self.cursor_position });
+        }
+
+        if self.status.is_none() {
+            self.count_down(elapsed_time);
+        }
+
+        mem::replace(&mut self.events, vec![])
+    }
+
+    /// Runs the clock down in a timed game, locking the terminal when it runs out.
+    fn count_down(&mut self, elapsed_time: Duration) {
+        let time_left = match self.time_left {
+            Some(time_left) => time_left - elapsed_time,
+            None => return,
+        };
+
+        if time_left <= Duration::zero() {
+            self.time_left = Some(Duration::zero());
+            self.status = Some(GameEnding::Lost);
+            self.events.push(GameEvent::LockedOut);
+        } else {
+            self.time_left = Some(time_left);
+        }
+    }
+
+    /// How far back the console history can be scrolled before the oldest entry reaches the top.
+    pub fn max_console_scroll(&self) -> usize {
+        let rows = self.entries.iter().map(Entry::display_rows).fold(0, |a, b| a + b);
+        rows.saturating_sub(CONSOLE_ROWS as usize)
+    }
+
+    /// The likeness that a word scored when it was guessed, if it has been.
+    pub fn likeness_of(&self, word: &str) -> Option<i32> {
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                match *entry {
+                    Entry::Incorrect { word: ref guess, num_correct } if guess == word => {
+                        Some(num_correct)
+                    }
+                    _ => None,
+                }
+            })
+            .next()
+    }
+
+    /// Every console entry, oldest first, written out in full.
+    pub fn log(&self) -> Vec<String> {
+        self.entries.iter().flat_map(Entry::log_lines).collect()
     }
 
     pub fn get_cursor_column_index(&self) -> Option<usize> {
@@ -210,56 +512,101 @@ impl GameState {
         None
     }
 
-    pub fn new(difficulty: i32) -> GameState {
+    /// Sets up a game. Fails if the dictionary can't be read or doesn't have enough words of the
+    /// right length, which may only show once the difficulty has changed.
+    pub fn new(settings: &GameSettings) -> Result<GameState, String> {
         // Generate the (cosmetic) addresses along the left and right. We'll generate them between
         // F000 and F900 to get some "hexy" addresses.
-        let mut rng = rand::thread_rng();
+        let mut rng = settings.rng();
         let starting_address = rng.gen_range(0xF000, 0xF900);
         let mut addresses = (starting_address..).step(0xC);
 
-        let word_length = difficulty as usize;
-        let num_words = 12;
-        let words = GameState::generate_words(num_words, word_length);
+        let word_length = settings.difficulty as usize;
+        let words = match settings.word_pool {
+            Some(ref pool) => {
+                rand::sample(&mut rng, pool.iter().cloned(), settings.num_words as usize)
+            }
+            None => {
+                let dictionary = settings.dictionary
+                    .as_ref()
+                    .map_or(words::SYSTEM_DICTIONARY, |dictionary| dictionary);
+                GameState::generate_words(dictionary, settings.num_words, word_length, &mut rng)?
+            }
+        };
 
         let left_column = Column::new(addresses.by_ref().take(ROWS as usize).collect(),
-                                      &words[..words.len() / 2]);
+                                      &words[..words.len() / 2],
+                                      settings.brackets,
+                                      &mut rng);
         let right_column = Column::new(addresses.take(ROWS as usize).collect(),
-                                       &words[words.len() / 2..]);
+                                       &words[words.len() / 2..],
+                                       settings.brackets,
+                                       &mut rng);
 
         let mut words = left_column.words();
         words.extend(right_column.words());
         let correct_word = rand::sample(&mut rng, words.iter(), 1).first().unwrap().clone();
 
-        GameState {
-            attempts: 4,
+        Ok(GameState {
+            attempts: settings.attempts,
             columns: [left_column, right_column],
             cursor_position: (0, 0),
             correct_word: correct_word.clone(),
             is_playing: true,
             entries: vec![],
             status: None,
+            bypassed: false,
+            time_left: settings.time_limit,
+            command_line: None,
+            console_scroll: 0,
+            shell: Shell::new(),
+            events: vec![],
+            rng: rng,
+        })
+    }
+
+    fn generate_words<R: Rng>(dictionary: &str,
+                              num_words: i32,
+                              length: usize,
+                              rng: &mut R)
+                              -> Result<Vec<String>, String> {
+        let words = words::load(Path::new(dictionary), length)
+            .map_err(|e| format!("{}: {}", dictionary, e))?;
+        if words.len() < num_words as usize {
+            return Err(format!("{} has {} playable words of {} letters, but a game needs {}",
+                               dictionary,
+                               words.len(),
+                               length,
+                               num_words));
         }
+        Ok(rand::sample(rng, words.into_iter(), num_words as usize))
     }
 
-    fn generate_words(num_words: i32, length: usize) -> Vec<String> {
-        let dict = File::open("/usr/share/dict/words").unwrap();
-        let words = BufReader::new(dict)
-            .lines()
-            .map(|word| word.unwrap())
-            .filter(|word| word.chars().count() == length)
-            .filter(|word| word.is_ascii())
-            .filter(|word| word.chars().next().unwrap().is_lowercase())
-            .filter(|word| word.chars().all(|c: char| c.is_alphabetic()));
 
-        let mut rng = rand::thread_rng();
-        rand::sample(&mut rng, words, num_words as usize)
-            .iter()
-            .map(|s| s.clone())
-            .collect()
-    }
+    fn run_command(&mut self, command: &str) {
+        if command.trim().is_empty() {
+            return;
+        }
+
+        let output = self.shell.run(command);
+        let mut lines = commands::console_lines(&command.to_uppercase());
+        for line in &output.lines {
+            lines.extend(commands::console_lines(line));
+        }
+        self.entries.push(Entry::Command { lines: lines });
 
+        if output.bypassed {
+            self.status = Some(GameEnding::Won);
+            self.bypassed = true;
+            self.events.push(GameEvent::Won { bypassed: true });
+        }
+    }
 
     fn select_entity(&mut self) {
+        // Once the game is over the board is gone, and a click must not guess what isn't seen.
+        if self.status.is_some() {
+            return;
+        }
         if let Some(entity) = self.get_entity_at_cursor().cloned() {
             match entity {
                 CursorEntity::Word { word, .. } => self.guess_word(&word),
@@ -268,23 +615,61 @@ impl GameState {
         }
     }
 
-    fn remove_dud(&mut self) {
-        let mut entities: Vec<&mut CursorEntity> = vec![];
-        for (i, column) in &mut self.columns.iter_mut().enumerate() {
-            entities.extend(column.entities.get_mut(i));
+    /// Shows the likeness of the word under the cursor to each word guessed so far, which the
+    /// password must share.
+    fn inspect_entity(&mut self) {
+        let word = match self.get_entity_at_cursor() {
+            Some(&CursorEntity::Word { ref word, guessed: false, .. }) => word.clone(),
+            _ => return,
+        };
+
+        let mut lines = commands::console_lines(&words::to_upper(&word));
+        let guesses = self.entries
+            .iter()
+            .filter_map(|entry| {
+                match *entry {
+                    Entry::Incorrect { ref word, .. } => Some(word.clone()),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        if guesses.is_empty() {
+            lines.extend(message_lines(&i18n::message("no-guesses")));
+        } else {
+            lines.extend(message_lines(&i18n::message("likeness")));
+            for guess in guesses {
+                let likeness = words::likeness(&word, &guess);
+                lines.extend(commands::console_lines(&format!("{}={}",
+                                                              words::to_upper(&guess),
+                                                              likeness)));
+            }
         }
 
-        for entity in entities {
-            match *entity {
-                CursorEntity::Word { ref word, ref mut removed, .. } => {
-                    if *word != self.correct_word && !*removed {
-                        *removed = true;
-                        return;
+        // Inspecting one word after another replaces the last answer rather than filling the
+        // console, and doesn't scroll it back down.
+        if let Some(&mut Entry::Likeness { lines: ref mut last }) = self.entries.last_mut() {
+            *last = lines;
+            return;
+        }
+        self.entries.push(Entry::Likeness { lines: lines });
+    }
+
+    /// Removes a word at random that isn't the password and hasn't been guessed.
+    fn remove_dud(&mut self) {
+        let mut duds: Vec<&mut bool> = vec![];
+        for column in &mut self.columns {
+            for entity in &mut column.entities {
+                if let CursorEntity::Word { ref word, guessed, ref mut removed, .. } = *entity {
+                    if *word != self.correct_word && !guessed && !*removed {
+                        duds.push(removed);
                     }
                 }
-                _ => continue,
             }
         }
+
+        if let Some(removed) = self.rng.choose_mut(&mut duds) {
+            **removed = true;
+        }
     }
 
     fn trigger_brackets(&mut self) {
@@ -295,15 +680,17 @@ impl GameState {
             _ => panic!("expected brackets to be under cursor"),
         }
 
-        let mut rng = rand::thread_rng();
-        let replenish_allowance = rng.gen_weighted_bool(3);
-        if replenish_allowance {
+        let replenish_allowance = self.rng.gen_weighted_bool(3);
+        let reward = if replenish_allowance {
             self.entries.push(Entry::AllowanceReplenish);
             self.attempts = STARTING_ATTEMPTS;
+            BracketReward::AllowanceReplenished
         } else {
             self.entries.push(Entry::DudRemoval);
             self.remove_dud();
-        }
+            BracketReward::DudRemoved
+        };
+        self.events.push(GameEvent::BracketTriggered { reward: reward });
     }
 
     fn guess_word(&mut self, word: &str) {
@@ -318,22 +705,47 @@ impl GameState {
         if word == self.correct_word {
             self.entries.push(Entry::Correct { word: word.to_string() });
             self.status = Some(GameEnding::Won);
+            self.events.push(GameEvent::WordGuessed {
+                word: word.to_string(),
+                likeness: words::length(word) as i32,
+                correct: true,
+            });
+            self.events.push(GameEvent::Won { bypassed: false });
         } else {
-            let num_correct = word.chars()
-                .enumerate()
-                .filter(|&(i, c)| c == self.correct_word.chars().nth(i).unwrap())
-                .count();
+            let num_correct = words::likeness(word, &self.correct_word);
             self.entries.push(Entry::Incorrect {
                 word: word.to_string(),
                 num_correct: num_correct as i32,
             });
+            self.events.push(GameEvent::WordGuessed {
+                word: word.to_string(),
+                likeness: num_correct as i32,
+                correct: false,
+            });
             if self.attempts == 0 {
                 self.status = Some(GameEnding::Lost);
+                self.events.push(GameEvent::LockedOut);
             }
         }
     }
 }
 
+/// Breaks a message into console lines between words, starting each with a prompt.
+fn message_lines(text: &str) -> Vec<String> {
+    i18n::wrap(text, CONSOLE_WIDTH as usize - 1)
+        .into_iter()
+        .map(|line| format!(">{}", line))
+        .collect()
+}
+
+/// Where a character of a column's word data is drawn on the screen, as an `(x, y)` position.
+pub fn screen_position(column_index: usize, index: usize) -> (i32, i32) {
+    let x = MARGIN + column_index as i32 * (COLUMN_WIDTH + COLUMN_PADDING) +
+            ADDRESS_COLUMN_WIDTH + INNER_COLUMN_PADDING + index as i32 % WORD_COLUMN_WIDTH;
+    let y = COLUMN_START_ROW + index as i32 / WORD_COLUMN_WIDTH;
+    (x, y)
+}
+
 pub struct Column {
     pub addresses: Vec<u16>,
     word_data: [char; CHARACTERS_PER_COLUMN as usize],
@@ -341,6 +753,11 @@ pub struct Column {
 }
 
 impl Column {
+    /// The words and bracket pairs in the column, in no particular order.
+    pub fn entities(&self) -> &[CursorEntity] {
+        &self.entities
+    }
+
     fn words(&self) -> Vec<String> {
         self.entities
             .iter()
@@ -360,9 +777,7 @@ impl Column {
         for entity in &self.entities {
             match *entity {
                 CursorEntity::Word { ref word, guessed, index, removed, .. } => {
-                    for (char_index, character) in word.to_ascii_uppercase()
-                        .chars()
-                        .enumerate() {
+                    for (char_index, character) in words::to_upper(word).chars().enumerate() {
                         let char_position: usize = index + char_index;
                         data[char_position] = if !guessed && !removed {
                             character
@@ -389,12 +804,15 @@ impl Column {
         data.into_iter().collect::<String>()
     }
 
-    fn new(addresses: Vec<u16>, words: &[String]) -> Column {
-        let word_length = words.iter().next().unwrap().len();
+    fn new<R: Rng>(addresses: Vec<u16>,
+                   words: &[String],
+                   num_brackets: i32,
+                   rng: &mut R)
+                   -> Column {
+        let word_length = words::length(words.iter().next().unwrap());
         let word_entities = words.iter()
             .enumerate()
             .map(|(index, word)| {
-                let mut rng = rand::thread_rng();
                 let chars_available = CHARACTERS_PER_COLUMN as usize / words.len();
                 let offset: usize = rng.gen_range(0, chars_available - word_length);
                 CursorEntity::Word {
@@ -406,13 +824,13 @@ impl Column {
             })
             .collect::<Vec<CursorEntity>>();
 
-        let brackets = Column::generate_brackets(8, &word_entities);
+        let brackets = Column::generate_brackets(num_brackets, &word_entities, rng);
         let mut entities = vec![];
 
         entities.extend(brackets);
         entities.extend(word_entities);
 
-        let garbage_characters = Column::generate_characters();
+        let garbage_characters = Column::generate_characters(rng);
 
         Column {
             addresses: addresses,
@@ -421,22 +839,24 @@ impl Column {
         }
     }
 
-    fn generate_characters() -> [char; CHARACTERS_PER_COLUMN as usize] {
+    fn generate_characters<R: Rng>(rng: &mut R) -> [char; CHARACTERS_PER_COLUMN as usize] {
         const GARBAGE_CHARACTERS: &'static str = r",|\!@#$%^&*-_+=.:;?,/";
 
         const NUM_CHARS: usize = CHARACTERS_PER_COLUMN as usize;
         let mut characters = ['\0'; NUM_CHARS];
-        let mut rng = rand::thread_rng();
 
         let range = Range::new(0, GARBAGE_CHARACTERS.len());
         for character in characters.iter_mut() {
-            let index = range.ind_sample(&mut rng);
+            let index = range.ind_sample(rng);
             *character = GARBAGE_CHARACTERS.chars().nth(index).unwrap();
         }
         characters
     }
 
-    fn generate_brackets(num_brackets: i32, words: &[CursorEntity]) -> Vec<CursorEntity> {
+    fn generate_brackets<R: Rng>(num_brackets: i32,
+                                 words: &[CursorEntity],
+                                 rng: &mut R)
+                                 -> Vec<CursorEntity> {
         const PAIRS: [(char, char); 4] = [('<', '>'), ('[', ']'), ('{', '}'), ('(', ')')];
 
         let bracket_length = 8;
@@ -451,13 +871,12 @@ impl Column {
                     }
                 })
         });
-        let mut rng = rand::thread_rng();
         let range = Range::new(0, PAIRS.len());
-        rand::sample(&mut rng, valid_indices, num_brackets as usize)
+        rand::sample(rng, valid_indices, num_brackets as usize)
             .iter()
             .map(|&index| {
                 CursorEntity::Brackets {
-                    pair: PAIRS[range.ind_sample(&mut rng)],
+                    pair: PAIRS[range.ind_sample(rng)],
                     indices: (index, index + bracket_length),
                     consumed: false,
                 }
@@ -465,3 +884,171 @@ impl Column {
             .collect::<Vec<CursorEntity>>()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ::rand::{SeedableRng, StdRng};
+    use ::time::Duration;
+
+    use super::{screen_position, CursorEntity, Entry, GameEnding, GameEvent, GameSettings,
+                GameState, InputEvent};
+
+    fn game() -> GameState {
+        let words = ["alpha", "bravo", "delta", "gamma", "omega", "sigma"];
+        let settings = GameSettings {
+            seed: 1,
+            num_words: words.len() as i32,
+            word_pool: Some(words.iter().map(|word| word.to_string()).collect()),
+            ..GameSettings::new(5)
+        };
+        GameState::new(&settings).unwrap()
+    }
+
+    /// The words on the board that haven't been guessed or removed, other than the password.
+    fn duds(game_state: &GameState) -> Vec<String> {
+        let mut duds = vec![];
+        for column in &game_state.columns {
+            for entity in column.entities() {
+                if let CursorEntity::Word { ref word, guessed, removed, .. } = *entity {
+                    if *word != game_state.correct_word && !guessed && !removed {
+                        duds.push(word.clone());
+                    }
+                }
+            }
+        }
+        duds
+    }
+
+    /// The words on the board, with where they are on the screen.
+    fn positions(game_state: &GameState) -> Vec<(String, (i32, i32))> {
+        let mut positions = vec![];
+        for (column_index, column) in game_state.columns.iter().enumerate() {
+            for entity in column.entities() {
+                if let CursorEntity::Word { ref word, index, .. } = *entity {
+                    positions.push((word.clone(), screen_position(column_index, index)));
+                }
+            }
+        }
+        positions
+    }
+
+    #[test]
+    fn removing_a_dud_spares_the_password_and_guessed_words() {
+        let mut game_state = game();
+        let spared = duds(&game_state).pop().unwrap();
+        for column in &mut game_state.columns {
+            for entity in &mut column.entities {
+                if let CursorEntity::Word { ref word, ref mut guessed, .. } = *entity {
+                    *guessed = *word != spared;
+                }
+            }
+        }
+
+        game_state.remove_dud();
+        for column in &game_state.columns {
+            for entity in column.entities() {
+                if let CursorEntity::Word { ref word, guessed, removed, .. } = *entity {
+                    assert_eq!(removed, *word == spared);
+                    assert!(!(guessed && removed));
+                }
+            }
+        }
+
+        // Once there are no duds left, nothing more is removed.
+        game_state.remove_dud();
+        assert!(duds(&game_state).is_empty());
+    }
+
+    #[test]
+    fn duds_are_removed_at_random() {
+        let first_removed = (0..20)
+            .map(|seed| {
+                // The same board every time, but what happens on it differs.
+                let mut game_state = game();
+                game_state.rng = StdRng::from_seed(&[seed]);
+                let before = duds(&game_state);
+                game_state.remove_dud();
+                let after = duds(&game_state);
+                before.into_iter().find(|word| !after.contains(word)).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert!(first_removed.iter().any(|word| *word != first_removed[0]));
+    }
+
+    #[test]
+    fn inspecting_again_replaces_the_last_likeness() {
+        let mut game_state = game();
+        let positions = positions(&game_state);
+
+        let likenesses = |game_state: &GameState| {
+            game_state.entries
+                .iter()
+                .filter(|entry| match **entry {
+                    Entry::Likeness { .. } => true,
+                    _ => false,
+                })
+                .count()
+        };
+        for &(_, (x, y)) in &positions {
+            game_state.update(Some(InputEvent::InspectAt(x, y)), Duration::zero());
+        }
+        assert_eq!(likenesses(&game_state), 1);
+        assert_eq!(game_state.entries.len(), 1);
+
+        // Scrolling back through the console isn't undone by another look.
+        game_state.entries.insert(0, Entry::DudRemoval);
+        game_state.entries.insert(0, Entry::DudRemoval);
+        game_state.entries.insert(0, Entry::DudRemoval);
+        game_state.console_scroll = 1;
+        let (x, y) = positions[0].1;
+        game_state.update(Some(InputEvent::InspectAt(x, y)), Duration::zero());
+        assert_eq!(game_state.console_scroll, 1);
+        assert_eq!(likenesses(&game_state), 1);
+    }
+
+    #[test]
+    fn getting_around_the_password_is_a_bypassed_win() {
+        let mut game_state = game();
+        let commands = ["SET TERMINAL/INQUIRE",
+                        "SET FILE/PROTECTION=OWNER:RWED ACCOUNTS.F",
+                        "SET HALT RESTART/MAINT",
+                        "RUN DEBUG/ACCOUNTS.F"];
+        let mut events = vec![];
+        for command in &commands {
+            game_state.update(Some(InputEvent::CommandPrompt), Duration::zero());
+            for c in command.chars() {
+                game_state.update(Some(InputEvent::Type(c)), Duration::zero());
+            }
+            events = game_state.update(Some(InputEvent::Action), Duration::zero());
+        }
+        assert_eq!(events, vec![GameEvent::Won { bypassed: true }]);
+        assert_eq!(game_state.status, Some(GameEnding::Won));
+        assert!(game_state.bypassed);
+    }
+
+    #[test]
+    fn clicking_after_the_game_is_over_does_nothing() {
+        let mut game_state = game();
+        let positions = positions(&game_state);
+        let click = |game_state: &mut GameState, (x, y)| {
+            game_state.update(Some(InputEvent::SelectAt(x, y)), Duration::zero())
+        };
+
+        let password = positions.iter().find(|&&(ref word, _)| *word == game_state.correct_word);
+        click(&mut game_state, password.unwrap().1);
+        assert_eq!(game_state.status, Some(GameEnding::Won));
+
+        let attempts = game_state.attempts;
+        let entries = game_state.entries.len();
+        for &(_, position) in &positions {
+            let events = click(&mut game_state, position);
+            assert!(events.iter().all(|event| match *event {
+                GameEvent::CursorMoved { .. } => true,
+                _ => false,
+            }));
+        }
+        assert_eq!(game_state.status, Some(GameEnding::Won));
+        assert_eq!(game_state.attempts, attempts);
+        assert_eq!(game_state.entries.len(), entries);
+    }
+}
@@ -0,0 +1,267 @@
+//! Plays the game without a screen, for bots and other tools. Commands are read from stdin one
+//! per line, and everything that happens is written to stdout as one JSON object per line.
+
+use std::io::{self, BufRead};
+
+use ::rustc_serialize::json::{Json, Object};
+use ::time::{Duration, PreciseTime};
+
+use ::game::{self, BracketReward, CursorEntity, Entry, GameEnding, GameEvent, GameSettings,
+             GameState, InputEvent};
+use ::words;
+
+fn object(fields: Vec<(&str, Json)>) -> Json {
+    let fields = fields.into_iter().map(|(name, value)| (name.to_string(), value));
+    Json::Object(fields.collect::<Object>())
+}
+
+fn string(text: &str) -> Json {
+    Json::String(text.to_string())
+}
+
+fn position((x, y): (i32, i32)) -> Json {
+    object(vec![("x", Json::I64(x as i64)), ("y", Json::I64(y as i64))])
+}
+
+fn write(json: &Json) {
+    println!("{}", json);
+}
+
+fn write_error(message: &str) {
+    write(&object(vec![("type", string("error")), ("message", string(message))]));
+}
+
+fn event_json(event: &GameEvent) -> Json {
+    let mut fields = vec![("type", string("event"))];
+    match *event {
+        GameEvent::CursorMoved { position: moved_to } => {
+            fields.push(("event", string("cursor-moved")));
+            fields.push(("position", position(moved_to)));
+        }
+        GameEvent::WordGuessed { ref word, likeness, correct } => {
+            fields.push(("event", string("word-guessed")));
+            fields.push(("word", string(word)));
+            fields.push(("likeness", Json::I64(likeness as i64)));
+            fields.push(("correct", Json::Boolean(correct)));
+        }
+        GameEvent::BracketTriggered { reward } => {
+            let reward = match reward {
+                BracketReward::DudRemoved => "dud-removed",
+                BracketReward::AllowanceReplenished => "allowance-replenished",
+            };
+            fields.push(("event", string("bracket-triggered")));
+            fields.push(("reward", string(reward)));
+        }
+        GameEvent::Won { .. } => fields.push(("event", string("won"))),
+        GameEvent::LockedOut => fields.push(("event", string("locked-out"))),
+    }
+    object(fields)
+}
+
+fn entry_json(entry: &Entry) -> Json {
+    match *entry {
+        Entry::Correct { ref word } => {
+            object(vec![("kind", string("correct")), ("word", string(word))])
+        }
+        Entry::Incorrect { ref word, num_correct } => {
+            object(vec![("kind", string("incorrect")),
+                        ("word", string(word)),
+                        ("likeness", Json::I64(num_correct as i64))])
+        }
+        Entry::DudRemoval => object(vec![("kind", string("dud-removed"))]),
+        Entry::AllowanceReplenish => object(vec![("kind", string("allowance-replenished"))]),
+        Entry::Command { .. } | Entry::Likeness { .. } => {
+            let kind = match *entry {
+                Entry::Command { .. } => "command",
+                _ => "likeness",
+            };
+            let lines = entry.log_lines().iter().map(|line| string(line)).collect();
+            object(vec![("kind", string(kind)), ("lines", Json::Array(lines))])
+        }
+    }
+}
+
+/// Everything a player could see on the screen. Nothing that gives the password away is
+/// included, not even the seed.
+fn state_json(game_state: &GameState) -> Json {
+    let mut words = vec![];
+    let mut brackets = vec![];
+    for (column_index, column) in game_state.columns.iter().enumerate() {
+        let data = column.render_word_data().chars().collect::<Vec<_>>();
+        let mut entities = column.entities().to_vec();
+        entities.sort_by_key(|entity| entity.indices().0);
+
+        for entity in entities {
+            let (start, end) = entity.indices();
+            let start_position = position(game::screen_position(column_index, start));
+            match entity {
+                CursorEntity::Word { word, guessed, removed, .. } => {
+                    words.push(object(vec![("word", Json::String(word)),
+                                           ("position", start_position),
+                                           ("guessed", Json::Boolean(guessed)),
+                                           ("removed", Json::Boolean(removed))]));
+                }
+                CursorEntity::Brackets { consumed, .. } => {
+                    let text = data[start..end].iter().cloned().collect();
+                    brackets.push(object(vec![("text", Json::String(text)),
+                                              ("position", start_position),
+                                              ("consumed", Json::Boolean(consumed))]));
+                }
+            }
+        }
+    }
+
+    let status = match game_state.status {
+        Some(GameEnding::Won) => "won",
+        Some(GameEnding::Lost) => "lost",
+        None => "playing",
+    };
+    let time_left = match game_state.time_left {
+        Some(time_left) => Json::I64(time_left.num_milliseconds()),
+        None => Json::Null,
+    };
+    let entries = game_state.entries.iter().map(entry_json).collect();
+
+    object(vec![("type", string("state")),
+                ("status", string(status)),
+                ("attempts", Json::I64(game_state.attempts as i64)),
+                ("time_left", time_left),
+                ("cursor", position(game_state.cursor_position)),
+                ("words", Json::Array(words)),
+                ("brackets", Json::Array(brackets)),
+                ("entries", Json::Array(entries))])
+}
+
+/// Reads `move up|down|left|right` or `move X Y`.
+fn move_input(arguments: &[&str]) -> Result<InputEvent, String> {
+    match arguments.len() {
+        1 => {
+            match arguments[0] {
+                "up" => Ok(InputEvent::Up),
+                "down" => Ok(InputEvent::Down),
+                "left" => Ok(InputEvent::Left),
+                "right" => Ok(InputEvent::Right),
+                direction => Err(format!("unknown direction '{}'", direction)),
+            }
+        }
+        2 => coordinates(arguments).map(|(x, y)| InputEvent::MoveTo(x, y)),
+        _ => Err("expected `move up|down|left|right` or `move X Y`".to_string()),
+    }
+}
+
+/// Reads `select`, for whatever is under the cursor, or `select X Y`.
+fn select_input(arguments: &[&str]) -> Result<InputEvent, String> {
+    match arguments.len() {
+        0 => Ok(InputEvent::Action),
+        2 => coordinates(arguments).map(|(x, y)| InputEvent::SelectAt(x, y)),
+        _ => Err("expected `select` or `select X Y`".to_string()),
+    }
+}
+
+fn coordinates(arguments: &[&str]) -> Result<(i32, i32), String> {
+    match (arguments[0].parse(), arguments[1].parse()) {
+        (Ok(x), Ok(y)) => Ok((x, y)),
+        _ => Err(format!("expected a position, not '{} {}'", arguments[0], arguments[1])),
+    }
+}
+
+/// Reads `guess WORD`, for a word still on the board.
+fn guess_input(game_state: &GameState, arguments: &[&str]) -> Result<InputEvent, String> {
+    if arguments.len() != 1 {
+        return Err("expected `guess WORD`".to_string());
+    }
+    let word = words::normalize(arguments[0]).unwrap_or_else(|| arguments[0].to_string());
+
+    for (column_index, column) in game_state.columns.iter().enumerate() {
+        for entity in column.entities() {
+            if let CursorEntity::Word { word: ref candidate, guessed, removed, index } = *entity {
+                if *candidate == word && !guessed && !removed {
+                    let (x, y) = game::screen_position(column_index, index);
+                    return Ok(InputEvent::SelectAt(x, y));
+                }
+            }
+        }
+    }
+    Err(format!("'{}' is not on the board, or has already been guessed", arguments[0]))
+}
+
+/// Turns the tool away from a terminal that is too hard for the player's skill.
+pub fn refuse(lines: &[String]) {
+    let lines = lines.iter().filter(|line| !line.is_empty()).cloned().collect::<Vec<_>>();
+    write_error(&lines.join(" "));
+}
+
+/// Plays a game driven by commands on stdin: `move`, `select`, `guess WORD`, `state`, and `new`
+/// to give up on the game and start another. Every command but `new` is answered with the
+/// events it caused followed by the state of the game, or with an error. The state is also
+/// written when the game starts. Returns whether another game was asked for.
+pub fn play(settings: &GameSettings) -> bool {
+    let mut game_state = match GameState::new(settings) {
+        Ok(game_state) => game_state,
+        Err(e) => {
+            write_error(&e);
+            return false;
+        }
+    };
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    write(&state_json(&game_state));
+
+    let mut last_time = PreciseTime::now();
+    loop {
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => return false,
+        };
+
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command.to_lowercase(),
+            None => continue,
+        };
+        let arguments = words.collect::<Vec<_>>();
+
+        // The clock runs while the tool thinks, and may have run out before it answered.
+        let elapsed = last_time.to(PreciseTime::now());
+        last_time = PreciseTime::now();
+        let mut events = if game_state.status.is_none() {
+            game_state.update(None, elapsed)
+        } else {
+            vec![]
+        };
+
+        let input = match &command[..] {
+            "state" => None,
+            "new" => return true,
+            "move" | "select" | "guess" if game_state.status.is_some() => {
+                Some(Err("the game is over; send `new` to play another".to_string()))
+            }
+            "move" => Some(move_input(&arguments)),
+            "select" => Some(select_input(&arguments)),
+            "guess" => Some(guess_input(&game_state, &arguments)),
+            command => Some(Err(format!("unknown command '{}'", command))),
+        };
+
+        match input {
+            Some(Err(message)) => {
+                // The clock may have locked the terminal before the command was refused, which
+                // the tool still needs to hear about.
+                if !events.is_empty() {
+                    for event in &events {
+                        write(&event_json(event));
+                    }
+                    write(&state_json(&game_state));
+                }
+                write_error(&message);
+                continue;
+            }
+            Some(Ok(input)) => events.extend(game_state.update(Some(input), Duration::zero())),
+            None => (),
+        }
+        for event in &events {
+            write(&event_json(event));
+        }
+        write(&state_json(&game_state));
+    }
+}
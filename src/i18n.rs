@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use ::keyfile;
+
+/// The names of the locales that are always available.
+pub const BUILTIN_LOCALES: [&'static str; 4] = ["en", "de", "fr", "es"];
+
+/// The locale that every other falls back to for messages it doesn't have.
+const FALLBACK_LOCALE: &'static str = "en";
+
+thread_local! {
+    // Text is looked up from all over the interface, so the catalog in use is kept here rather
+    // than passed around.
+    static CATALOG: RefCell<Catalog> = RefCell::new(Catalog::builtin(FALLBACK_LOCALE).unwrap());
+    static FALLBACK: Catalog = Catalog::builtin(FALLBACK_LOCALE).unwrap();
+}
+
+/// The messages shown on the terminal, in one language.
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    pub name: String,
+    messages: BTreeMap<String, String>,
+}
+
+impl Catalog {
+    fn builtin(name: &str) -> Option<Catalog> {
+        let source = match name {
+            "en" => include_str!("../resources/locales/en.txt"),
+            "de" => include_str!("../resources/locales/de.txt"),
+            "fr" => include_str!("../resources/locales/fr.txt"),
+            "es" => include_str!("../resources/locales/es.txt"),
+            _ => return None,
+        };
+        Some(Catalog::parse(name, source).expect("built-in catalogs are valid"))
+    }
+
+    /// Looks up a locale by name. If there is no built-in locale with that name, the name is
+    /// treated as the path to a catalog file.
+    pub fn load(name: &str) -> Result<Catalog, String> {
+        if let Some(catalog) = Catalog::builtin(name) {
+            return Ok(catalog);
+        }
+
+        let path = Path::new(name);
+        if !path.is_file() {
+            return Err(format!("unknown locale '{}' (expected one of {} or a catalog file)",
+                               name,
+                               BUILTIN_LOCALES.join(", ")));
+        }
+
+        let mut source = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut source))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Catalog::parse(&path.display().to_string(), &source)
+    }
+
+    /// Reads a catalog. A catalog contains `key = message` lines, where `{0}`, `{1}` and so on in
+    /// a message are replaced by values from the game. Blank lines and lines starting with `#`
+    /// are ignored.
+    fn parse(name: &str, source: &str) -> Result<Catalog, String> {
+        let mut messages = BTreeMap::new();
+        keyfile::parse(name, source, "key = message", |_, key, message| {
+            messages.insert(key.to_string(), message.to_string());
+            Ok(())
+        })?;
+
+        Ok(Catalog {
+            name: name.to_string(),
+            messages: messages,
+        })
+    }
+}
+
+/// The built-in locale matching the environment's language, or English.
+pub fn system_locale() -> &'static str {
+    for variable in &["LC_ALL", "LC_MESSAGES", "LANG"] {
+        if let Ok(value) = env::var(variable) {
+            let locale = BUILTIN_LOCALES.iter().find(|&&locale| value.starts_with(locale));
+            if let Some(&locale) = locale {
+                return locale;
+            }
+        }
+    }
+    FALLBACK_LOCALE
+}
+
+/// Switches every message to a different language.
+pub fn set_catalog(catalog: Catalog) {
+    info!("Using locale '{}'.", catalog.name);
+    CATALOG.with(|current| *current.borrow_mut() = catalog);
+}
+
+/// Looks up a message, falling back to English and then to the key itself.
+pub fn message(key: &str) -> String {
+    CATALOG.with(|catalog| catalog.borrow().messages.get(key).cloned())
+        .or_else(|| FALLBACK.with(|fallback| fallback.messages.get(key).cloned()))
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Looks up a message and fills in its values.
+pub fn message_with(key: &str, values: &[&Display]) -> String {
+    let mut text = message(key);
+    for (i, value) in values.iter().enumerate() {
+        text = text.replace(&format!("{{{}}}", i), &value.to_string());
+    }
+    text
+}
+
+/// Breaks text into lines no wider than `width`, between words where possible.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word.chars().collect::<Vec<_>>();
+
+        // Words too long for a line of their own are broken wherever they reach the edge.
+        while word.len() > width {
+            if !line.is_empty() {
+                lines.push(line);
+                line = String::new();
+            }
+            lines.push(word.drain(..width).collect());
+        }
+        if word.is_empty() {
+            continue;
+        }
+
+        let line_length = line.chars().count();
+        if line_length > 0 && line_length + 1 + word.len() > width {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
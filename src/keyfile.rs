@@ -0,0 +1,75 @@
+//! Reads the `key = value` files used for the configuration, themes, sound maps and catalogs.
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// Reads a file with `parse`, naming it by its path in errors.
+pub fn read<F>(path: &Path, expected: &str, each: F) -> Result<(), String>
+    where F: FnMut(usize, &str, &str) -> Result<(), String>
+{
+    let mut source = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut source))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse(&path.display().to_string(), &source, expected, each)
+}
+
+/// Calls `each` with the line number, key and value of every `key = value` line, both trimmed.
+/// Blank lines and lines starting with `#` are ignored. Lines that aren't `key = value` are
+/// reported with `expected`, and the errors `each` returns are prefixed with the name of the
+/// source and the line number.
+pub fn parse<F>(name: &str, source: &str, expected: &str, mut each: F) -> Result<(), String>
+    where F: FnMut(usize, &str, &str) -> Result<(), String>
+{
+    for (line_number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message: String| format!("{}:{}: {}", name, line_number + 1, message);
+
+        let mut parts = line.splitn(2, '=').map(str::trim);
+        match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) if !key.is_empty() => {
+                each(line_number + 1, key, value).map_err(&error)?;
+            }
+            _ => return Err(error(format!("expected `{}`", expected))),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    fn pairs(source: &str) -> Result<Vec<(usize, String, String)>, String> {
+        let mut pairs = vec![];
+        parse("test", source, "key = value", |line, key, value| {
+            pairs.push((line, key.to_string(), value.to_string()));
+            Ok(())
+        })?;
+        Ok(pairs)
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let pairs = pairs("# comment\n\n  a = 1 \nb=two = 2\n").unwrap();
+        assert_eq!(pairs,
+                   vec![(3, "a".to_string(), "1".to_string()),
+                        (4, "b".to_string(), "two = 2".to_string())]);
+    }
+
+    #[test]
+    fn reports_the_line_of_an_error() {
+        assert_eq!(pairs("a = 1\nb\n"), Err("test:2: expected `key = value`".to_string()));
+        assert_eq!(pairs("= 1\n"), Err("test:1: expected `key = value`".to_string()));
+
+        let result = parse("test", "\na = 1\n", "key = value", |_, key, _| {
+            Err(format!("unknown key '{}'", key))
+        });
+        assert_eq!(result, Err("test:2: unknown key 'a'".to_string()));
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use ::ncurses::*;
+
+use ::consts::CONSOLE_ROWS;
+use ::game::InputEvent;
+
+/// Everything in the game that can be bound to keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Control {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Quit,
+    ToggleMute,
+    Screenshot,
+    CommandPrompt,
+    ScrollUp,
+    ScrollDown,
+    ShowLog,
+}
+
+pub const CONTROLS: [Control; 12] = [Control::Up,
+                                     Control::Down,
+                                     Control::Left,
+                                     Control::Right,
+                                     Control::Select,
+                                     Control::Quit,
+                                     Control::ToggleMute,
+                                     Control::Screenshot,
+                                     Control::CommandPrompt,
+                                     Control::ScrollUp,
+                                     Control::ScrollDown,
+                                     Control::ShowLog];
+
+impl Control {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Control::Up => "up",
+            Control::Down => "down",
+            Control::Left => "left",
+            Control::Right => "right",
+            Control::Select => "select",
+            Control::Quit => "quit",
+            Control::ToggleMute => "mute",
+            Control::Screenshot => "screenshot",
+            Control::CommandPrompt => "prompt",
+            Control::ScrollUp => "scroll-up",
+            Control::ScrollDown => "scroll-down",
+            Control::ShowLog => "log",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Control> {
+        CONTROLS.iter().cloned().find(|control| control.name() == name)
+    }
+
+    pub fn input_event(&self) -> InputEvent {
+        match *self {
+            Control::Up => InputEvent::Up,
+            Control::Down => InputEvent::Down,
+            Control::Left => InputEvent::Left,
+            Control::Right => InputEvent::Right,
+            Control::Select => InputEvent::Action,
+            Control::Quit => InputEvent::Quit,
+            Control::ToggleMute => InputEvent::ToggleMute,
+            Control::Screenshot => InputEvent::Screenshot,
+            Control::CommandPrompt => InputEvent::CommandPrompt,
+
+            // The console scrolls a page at a time.
+            Control::ScrollUp => InputEvent::ScrollConsole(CONSOLE_ROWS),
+            Control::ScrollDown => InputEvent::ScrollConsole(-CONSOLE_ROWS),
+            Control::ShowLog => InputEvent::ShowLog,
+        }
+    }
+}
+
+const KEY_ESC: i32 = 27;
+const KEY_NEWLINE: i32 = '\n' as i32;
+
+/// Keys that are written by name rather than as the character they type.
+fn named_keys() -> Vec<(&'static str, i32)> {
+    let mut keys = vec![("up", KEY_UP),
+                        ("down", KEY_DOWN),
+                        ("left", KEY_LEFT),
+                        ("right", KEY_RIGHT),
+                        ("enter", KEY_NEWLINE),
+                        ("esc", KEY_ESC),
+                        ("space", ' ' as i32),
+                        ("tab", '\t' as i32),
+                        ("backspace", KEY_BACKSPACE),
+                        ("home", KEY_HOME),
+                        ("end", KEY_END),
+                        ("pageup", KEY_PPAGE),
+                        ("pagedown", KEY_NPAGE)];
+    keys.extend((1..13).map(|n| (FUNCTION_KEY_NAMES[n - 1], KEY_F(n as u8))));
+    keys
+}
+
+const FUNCTION_KEY_NAMES: [&'static str; 12] = ["f1", "f2", "f3", "f4", "f5", "f6", "f7", "f8",
+                                                "f9", "f10", "f11", "f12"];
+
+/// Reads a key name, such as `w`, `enter` or `f1`.
+pub fn parse_key(name: &str) -> Option<i32> {
+    let lower = name.to_lowercase();
+    if let Some(&(_, key)) = named_keys().iter().find(|&&(key_name, _)| key_name == lower) {
+        return Some(key);
+    }
+
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii() && !c.is_control() => Some(c as i32),
+        _ => None,
+    }
+}
+
+pub fn key_name(key: i32) -> String {
+    match named_keys().iter().find(|&&(_, named_key)| named_key == key) {
+        Some(&(name, _)) => name.to_string(),
+        None => (key as u8 as char).to_string(),
+    }
+}
+
+/// Which control each key is bound to.
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    bindings: BTreeMap<i32, Control>,
+}
+
+impl Default for KeyBindings {
+    /// The arrow keys, WASD and HJKL move, Enter selects, and Esc quits.
+    fn default() -> KeyBindings {
+        let defaults = [(Control::Up, "up w k"),
+                        (Control::Down, "down s j"),
+                        (Control::Left, "left a h"),
+                        (Control::Right, "right d l"),
+                        (Control::Select, "enter"),
+                        (Control::Quit, "esc"),
+                        (Control::ToggleMute, "m"),
+                        (Control::Screenshot, "p"),
+                        (Control::CommandPrompt, ": >"),
+                        (Control::ScrollUp, "pageup"),
+                        (Control::ScrollDown, "pagedown"),
+                        (Control::ShowLog, "tab")];
+
+        let mut bindings = BTreeMap::new();
+        for &(control, keys) in &defaults {
+            for key in keys.split_whitespace() {
+                bindings.insert(parse_key(key).unwrap(), control);
+            }
+        }
+        KeyBindings { bindings: bindings }
+    }
+}
+
+impl KeyBindings {
+    pub fn control(&self, key: i32) -> Option<Control> {
+        self.bindings.get(&key).cloned()
+    }
+
+    /// The keys bound to a control, in order.
+    pub fn keys(&self, control: Control) -> Vec<i32> {
+        self.bindings.iter().filter(|&(_, &c)| c == control).map(|(&key, _)| key).collect()
+    }
+
+    /// Replaces the keys bound to a control. Keys that were bound to another control are taken
+    /// over.
+    pub fn bind(&mut self, control: Control, keys: &[i32]) {
+        for key in self.keys(control) {
+            self.bindings.remove(&key);
+        }
+        for &key in keys {
+            self.bindings.insert(key, control);
+        }
+    }
+}
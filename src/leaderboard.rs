@@ -0,0 +1,208 @@
+use std::cmp;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+use ::time::{self, Duration};
+
+use ::i18n;
+use ::paths;
+use ::stats;
+
+/// How many scores are kept for each difficulty.
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// The longest name that can be entered on the leaderboard.
+pub const MAX_NAME_LENGTH: usize = 12;
+
+/// Works out the score for a won game. Harder games are worth more, and every attempt left over
+/// is a bonus. Fast solves earn up to five minutes' worth of time bonus, and every bracket pair
+/// used costs a little.
+pub fn score(difficulty: i32, attempts_left: i32, duration: Duration, brackets_used: i32) -> i32 {
+    let base = difficulty * 100;
+    let attempts_bonus = attempts_left * 50;
+    let time_bonus = cmp::max(0, 300 - duration.num_seconds() as i32);
+    let bracket_penalty = brackets_used * 25;
+    cmp::max(0, base + attempts_bonus + time_bonus - bracket_penalty)
+}
+
+#[derive(Debug, Clone)]
+pub struct Score {
+    pub score: i32,
+    pub name: String,
+
+    /// When the score was set, in seconds since the epoch.
+    pub timestamp: i64,
+    pub duration: Duration,
+}
+
+/// The best scores for one difficulty, best first. Each difficulty is kept in its own
+/// tab-separated file in the data directory.
+pub struct Leaderboard {
+    pub difficulty: i32,
+    pub scores: Vec<Score>,
+}
+
+fn leaderboard_file(difficulty: i32) -> String {
+    format!("leaderboard-{}.tsv", difficulty)
+}
+
+impl Leaderboard {
+    pub fn load(difficulty: i32) -> io::Result<Leaderboard> {
+        let path = paths::data_file(&leaderboard_file(difficulty))?;
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Leaderboard {
+                    difficulty: difficulty,
+                    scores: vec![],
+                })
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut scores = vec![];
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let fields = line.split('\t').collect::<Vec<_>>();
+            if fields.len() != 4 {
+                warn!("{}: skipping invalid score '{}'", path.display(), line);
+                continue;
+            }
+            match (fields[0].parse(), fields[2].parse(), fields[3].parse()) {
+                (Ok(score), Ok(timestamp), Ok(seconds)) => {
+                    scores.push(Score {
+                        score: score,
+                        name: fields[1].to_string(),
+                        timestamp: timestamp,
+                        duration: Duration::seconds(seconds),
+                    })
+                }
+                _ => warn!("{}: skipping invalid score '{}'", path.display(), line),
+            }
+        }
+
+        scores.sort_by(|a, b| b.score.cmp(&a.score));
+        Ok(Leaderboard {
+            difficulty: difficulty,
+            scores: scores,
+        })
+    }
+
+    /// Loads every leaderboard that has been saved, easiest first.
+    pub fn load_all() -> io::Result<Vec<Leaderboard>> {
+        let dir = match paths::data_dir() {
+            Some(dir) => dir,
+            None => return Ok(vec![]),
+        };
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        let mut difficulties = vec![];
+        for entry in entries {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if name.starts_with("leaderboard-") && name.ends_with(".tsv") {
+                let difficulty = &name["leaderboard-".len()..name.len() - ".tsv".len()];
+                if let Ok(difficulty) = difficulty.parse() {
+                    difficulties.push(difficulty);
+                }
+            }
+        }
+        difficulties.sort();
+
+        difficulties.into_iter().map(Leaderboard::load).collect()
+    }
+
+    /// Whether a score is good enough to be put on the board.
+    pub fn qualifies(&self, score: i32) -> bool {
+        self.scores.len() < LEADERBOARD_SIZE || self.scores.iter().any(|s| score > s.score)
+    }
+
+    /// Adds a score, dropping the worst one if the board is full.
+    pub fn insert(&mut self, score: Score) {
+        let position = self.scores.iter().position(|s| score.score > s.score);
+        match position {
+            Some(position) => self.scores.insert(position, score),
+            None => self.scores.push(score),
+        }
+        self.scores.truncate(LEADERBOARD_SIZE);
+    }
+
+    pub fn save(&self) -> io::Result<PathBuf> {
+        let path = paths::data_file(&leaderboard_file(self.difficulty))?;
+        paths::replace_file(&path, |writer| {
+            for score in &self.scores {
+                writeln!(writer,
+                         "{}\t{}\t{}\t{}",
+                         score.score,
+                         score.name,
+                         score.timestamp,
+                         score.duration.num_seconds())?;
+            }
+            Ok(())
+        })?;
+        Ok(path)
+    }
+
+    /// Formats the leaderboard as a table, one line per score.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![i18n::message_with("scores-difficulty", &[&self.difficulty])];
+        if self.scores.is_empty() {
+            lines.push(format!("  {}", i18n::message("scores-none-yet")));
+        }
+        for (rank, score) in self.scores.iter().enumerate() {
+            lines.push(format!("{:>3}. {:>5}  {:<width$}  {:>5}  {}",
+                               rank + 1,
+                               score.score,
+                               score.name.to_uppercase(),
+                               stats::format_duration(score.duration),
+                               stats::format_date(score.timestamp),
+                               width = MAX_NAME_LENGTH));
+        }
+        lines
+    }
+}
+
+/// Formats several leaderboards one after another, as shown by the `leaderboard` command.
+pub fn summary(leaderboards: &[Leaderboard]) -> Vec<String> {
+    if leaderboards.is_empty() {
+        return vec![i18n::message("scores-none")];
+    }
+
+    let mut lines = vec![];
+    for leaderboard in leaderboards {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(leaderboard.lines());
+    }
+    lines
+}
+
+/// Makes a name safe to store in a leaderboard file.
+pub fn clean_name(name: &str) -> String {
+    let name = name.chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME_LENGTH)
+        .collect::<String>();
+    let name = name.trim();
+    if name.is_empty() {
+        "ANONYMOUS".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// A score for a game that has just been won.
+pub fn new_score(score: i32, name: &str, duration: Duration) -> Score {
+    Score {
+        score: score,
+        name: clean_name(name),
+        timestamp: time::get_time().sec,
+        duration: duration,
+    }
+}
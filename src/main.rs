@@ -7,30 +7,213 @@ extern crate ncurses;
 extern crate rand;
 extern crate rustc_serialize;
 extern crate time;
+extern crate unicode_normalization;
+extern crate unicode_segmentation;
+extern crate unicode_width;
 
 mod game;
 mod window;
 mod consts;
+mod daily;
+mod leaderboard;
+mod markers;
+mod paths;
+mod race;
+mod stats;
+mod accessible;
+mod ansi;
+mod cast;
+mod commands;
+mod config;
+mod effects;
+mod headless;
+mod i18n;
+mod keyfile;
+mod keys;
+mod menu;
+mod mode;
+mod replay;
+mod screen;
+mod screenshot;
+mod skill;
+mod sound;
+mod telnet;
+mod terminal;
+mod theme;
+mod words;
+
+use std::cmp;
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use docopt::Docopt;
-use time::PreciseTime;
+use time::{Duration, PreciseTime};
+
+use cast::CastRecorder;
+use config::Config;
+use consts::{MAX_DIFFICULTY, MIN_DIFFICULTY, NUM_WORDS};
+use daily::{DailyStore, DailyTracker};
+use effects::EffectSettings;
+use game::{GameEnding, GameEvent, GameSettings, GameState, InputEvent, Subscriber};
+use i18n::Catalog;
+use leaderboard::Leaderboard;
+use markers::HighlightStyle;
+use menu::{Menu, MenuChoice};
+use mode::{EndlessRun, Mode};
+use race::RaceClient;
+use replay::{Recorder, Replay, ReplayControl};
+use screenshot::Format;
+use skill::{LockLevel, Skill};
+use sound::{Backend, SoundPlayer};
+use stats::{GameRecord, StatsStore, StatsTracker, Stopwatch};
+use telnet::TelnetWindow;
+use terminal::{Content, Flags, MenuEntry, TerminalScript};
+use theme::Theme;
+use window::{Page, Prompt, Window};
 
-use game::{GameState, InputEvent};
+/// The fastest and slowest that replays can be played back.
+const MAX_REPLAY_SPEED: f64 = 16.0;
+const MIN_REPLAY_SPEED: f64 = 0.125;
 
 static USAGE: &'static str = "
 Usage:
     robco-term [options]
+    robco-term stats
+    robco-term leaderboard [options]
+    robco-term daily
+    robco-term serve [options]
+    robco-term join <address> [options]
+    robco-term host [options]
     robco-term (-h | --help)
 
+Commands:
+    stats                           Show statistics about the games you've played.
+    daily                           Print a summary of today's daily terminal to share.
+    leaderboard                     Show the high scores for every difficulty, or only the one
+                                    given with --difficulty.
+    serve                           Host a race: wait for --players players to join, then start
+                                    them all on the same terminal and show how they're doing.
+    join                            Join the race hosted at <address>, given as HOST or HOST:PORT.
+    host                            Serve the game over telnet, with a session of its own for
+                                    every terminal that connects.
+
 Options:
     -h --help                       Show this screen.
-    -d LEVEL --difficulty=LEVEL     Set difficulty of the game (default 5). Currently this only
-                                    affects the length of potential passwords.
+    -d LEVEL --difficulty=LEVEL     Set difficulty of the game, from 4 to 12 (default 5).
+                                    Currently this only affects the length of potential passwords.
+    -t THEME --theme=THEME          Set the color theme: green, amber, white, blue, or the path to a
+                                    theme file (default green).
+    -l LOCALE --locale=LOCALE       Set the language of the terminal: en, de, fr, es, or the path
+                                    to a catalog file (default from the environment, or en).
+    --dictionary=FILE               Pick passwords from FILE, a list of words with one on each line,
+                                    instead of /usr/share/dict/words. Words may be in any language.
+    --effects                       Simulate an old CRT with scanlines, flicker and screen roll.
+    --no-effects                    Turn effects off, even if the configuration file turns them on.
+    --reduced-motion                Keep effects still: no flicker, roll or blinking cursor.
+    --no-reduced-motion             Let effects move, even if the configuration file stops them.
+    --highlight=STYLE               Set how the word under the cursor is highlighted: reverse,
+                                    underline or bold (default reverse).
+    --inline-likeness               Show the likeness of each guessed word on the board.
+    --no-inline-likeness            Don't show likenesses on the board, even if the configuration
+                                    file does.
+    --sound-command=CMD             Play sound files by running CMD with the file as its last
+                                    argument, e.g. \"aplay -q\". Without it, sounds ring the
+                                    terminal bell.
+    --sound-map=FILE                Choose the sound for each game event (see README).
+    --mute                          Start with sound muted. Press M to toggle during the game.
+    --no-mute                       Start with sound on, even if the configuration file mutes it.
+    -m MODE --mode=MODE             Set the game mode: classic, time-attack or endless (default
+                                    classic).
+    --time-limit=SECONDS            Set how long each terminal can be worked on in time-attack
+                                    mode (default 120).
+    --science=SKILL                 Set your Science skill, from 0 to 100 (default 50). Terminals
+                                    need a certain skill to hack, and are easier to hack the more
+                                    skill you have.
+    --lock-level=LEVEL              Set the lock level of terminals: very-easy, easy, average, hard
+                                    or very-hard (default average).
+    --terminal=FILE                 Hack a custom terminal described in a JSON file (see README).
+    --accessible                    Play in plain text, typing commands such as \"guess WORD\",
+                                    for use with a screen reader.
+    --headless-json                 Play without a screen, reading commands from stdin and writing
+                                    the game's state and events to stdout as JSON lines, for bots
+                                    and other tools (see README).
+    --record=FILE                   Record the first game to a replay file.
+    --replay=FILE                   Watch a recorded game. Space pauses, . steps through inputs
+                                    one at a time, and + and - change the speed.
+    --speed=SPEED                   Set how fast replays are played back (default 1).
+    --record-cast=FILE              Record everything drawn on the screen to an asciicast file.
+    --screenshot=FILE               Save the screen to FILE on exit, as text, HTML or SVG depending
+                                    on the extension.
+    --screenshot-format=FORMAT      Set the format of screenshots taken by pressing P: txt, html
+                                    or svg (default html).
+    --port=PORT                     Set the port to host a race on (default 7777), or to serve the
+                                    game on with host (default 2323).
+    --players=N                     Set how many players a race waits for (default 2).
+    --name=NAME                     Set your name on the race scoreboard (default $USER).
+    --print-config                  Print the configuration, with any options given here applied,
+                                    in the format of the configuration file.
+
+Defaults for the difficulty, theme, language, effects, highlighting and sound can be set in a
+configuration file, ~/.config/robco-term/config, along with the keys used to play and the
+markers drawn over guessed and removed words (see README).
 ";
 
 #[derive(Debug, RustcDecodable)]
 struct Args {
+    cmd_stats: bool,
+    cmd_leaderboard: bool,
+    cmd_daily: bool,
+    cmd_serve: bool,
+    cmd_join: bool,
+    cmd_host: bool,
+    arg_address: String,
     flag_difficulty: Option<i32>,
+    flag_mode: Option<String>,
+    flag_time_limit: Option<i64>,
+    flag_science: Option<i32>,
+    flag_lock_level: Option<String>,
+    flag_terminal: Option<String>,
+    flag_accessible: bool,
+    flag_headless_json: bool,
+    flag_theme: Option<String>,
+    flag_locale: Option<String>,
+    flag_dictionary: Option<String>,
+    flag_effects: bool,
+    flag_no_effects: bool,
+    flag_reduced_motion: bool,
+    flag_no_reduced_motion: bool,
+    flag_highlight: Option<String>,
+    flag_inline_likeness: bool,
+    flag_no_inline_likeness: bool,
+    flag_sound_command: Option<String>,
+    flag_sound_map: Option<String>,
+    flag_mute: bool,
+    flag_no_mute: bool,
+    flag_record: Option<String>,
+    flag_replay: Option<String>,
+    flag_speed: Option<f64>,
+    flag_record_cast: Option<String>,
+    flag_screenshot: Option<String>,
+    flag_screenshot_format: Option<String>,
+    flag_port: Option<u16>,
+    flag_players: Option<usize>,
+    flag_name: Option<String>,
+    flag_print_config: bool,
+}
+
+/// Applies a flag like `--effects` and its opposite, `--no-effects`, to a setting from the
+/// configuration file, which stays as it is unless one of them is given.
+fn switch(setting: &mut bool, name: &str, on: bool, off: bool) {
+    if on && off {
+        println!("--{0} and --no-{0} can't be used together", name);
+        process::exit(1);
+    }
+    if on || off {
+        *setting = on;
+    }
 }
 
 fn main() {
@@ -39,20 +222,1230 @@ fn main() {
     let args: Args = Docopt::new(USAGE)
         .and_then(|d| d.decode())
         .unwrap_or_else(|e| e.exit());
-    info!("Starting game.");
 
-    let mut game_state = GameState::new(args.flag_difficulty.unwrap_or(5));
-    let window = window::create();
+    // Options given on the command line take precedence over the configuration file.
+    let mut config = Config::load().unwrap_or_else(|e| {
+        println!("{}", e);
+        process::exit(1);
+    });
+    if let Some(difficulty) = args.flag_difficulty {
+        config.difficulty = difficulty;
+    }
+    if let Some(ref theme) = args.flag_theme {
+        config.theme = theme.clone();
+    }
+    if args.flag_locale.is_some() {
+        config.locale = args.flag_locale.clone();
+    }
+    if args.flag_dictionary.is_some() {
+        config.dictionary = args.flag_dictionary.clone();
+    }
+    switch(&mut config.effects,
+           "effects",
+           args.flag_effects,
+           args.flag_no_effects);
+    switch(&mut config.reduced_motion,
+           "reduced-motion",
+           args.flag_reduced_motion,
+           args.flag_no_reduced_motion);
+    if let Some(ref name) = args.flag_highlight {
+        config.markers.highlight = HighlightStyle::parse(name).unwrap_or_else(|| {
+            println!("unknown highlight '{}' (expected reverse, underline or bold)", name);
+            process::exit(1);
+        });
+    }
+    switch(&mut config.markers.inline_likeness,
+           "inline-likeness",
+           args.flag_inline_likeness,
+           args.flag_no_inline_likeness);
+    switch(&mut config.mute, "mute", args.flag_mute, args.flag_no_mute);
+    if args.flag_sound_command.is_some() {
+        config.sound_command = args.flag_sound_command.clone();
+    }
+    if args.flag_sound_map.is_some() {
+        config.sound_map = args.flag_sound_map.clone();
+    }
+
+    let difficulty = config.difficulty;
+    if difficulty < MIN_DIFFICULTY || difficulty > MAX_DIFFICULTY {
+        println!("difficulty must be between {} and {}", MIN_DIFFICULTY, MAX_DIFFICULTY);
+        process::exit(1);
+    }
+
+    if args.flag_print_config {
+        config.write(&mut io::stdout()).unwrap();
+        return;
+    }
+
+    let locale = config.locale.clone().unwrap_or_else(|| i18n::system_locale().to_string());
+    let catalog = Catalog::load(&locale).unwrap_or_else(|e| {
+        println!("{}", e);
+        process::exit(1);
+    });
+    i18n::set_catalog(catalog.clone());
+
+    // These print in the player's language, but need nothing else from the configuration.
+    if args.cmd_stats {
+        print_stats();
+        return;
+    }
+
+    if args.cmd_leaderboard {
+        print_leaderboards(args.flag_difficulty);
+        return;
+    }
+
+    if args.cmd_daily {
+        print_daily();
+        return;
+    }
+
+    let theme = Theme::load(&config.theme).unwrap_or_else(|e| {
+        println!("{}", e);
+        process::exit(1);
+    });
+
+    if args.cmd_serve {
+        serve_race(config.dictionary.as_ref().map_or(words::SYSTEM_DICTIONARY, |d| d),
+                   difficulty,
+                   args.flag_port.unwrap_or(race::DEFAULT_PORT),
+                   args.flag_players.unwrap_or(2));
+        return;
+    }
+
+    // Check the dictionary now rather than when the first game is about to start.
+    if let Some(ref dictionary) = config.dictionary {
+        match words::load(Path::new(dictionary), difficulty as usize) {
+            Ok(ref words) if words.len() >= NUM_WORDS as usize => (),
+            Ok(words) => {
+                println!("{} has {} playable words of {} letters, but a game needs {}",
+                         dictionary,
+                         words.len(),
+                         difficulty,
+                         NUM_WORDS);
+                process::exit(1);
+            }
+            Err(e) => {
+                println!("{}: {}", dictionary, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    // Sound files would be played on the host rather than at the players' terminals, so hosted
+    // sessions only ring the bell.
+    let backend = match config.sound_command {
+        Some(ref command) if !args.cmd_host => Backend::Command(command.clone()),
+        _ => Backend::Bell,
+    };
+    let mut sound = SoundPlayer::new(backend);
+    if let Some(ref path) = config.sound_map {
+        sound.load_sound_map(Path::new(path)).unwrap_or_else(|e| {
+            println!("{}", e);
+            process::exit(1);
+        });
+    }
+    if config.mute {
+        sound.toggle_mute();
+    }
+
+    let effects = EffectSettings {
+        enabled: config.effects,
+        reduced_motion: config.reduced_motion,
+    };
+
+    let cast = args.flag_record_cast.as_ref().map(|path| {
+        CastRecorder::create(Path::new(path), &theme).unwrap_or_else(|e| {
+            println!("{}: {}", path, e);
+            process::exit(1);
+        })
+    });
+
+    let screenshot_path = args.flag_screenshot.as_ref().map(PathBuf::from);
+    let screenshot_format = match args.flag_screenshot_format {
+        Some(ref name) => {
+            Format::parse(name).unwrap_or_else(|| {
+                println!("unknown screenshot format '{}' (expected txt, html or svg)", name);
+                process::exit(1);
+            })
+        }
+        None => Format::Html,
+    };
+
+    let replay = args.flag_replay.as_ref().map(|path| {
+        Replay::load(Path::new(path)).unwrap_or_else(|e| {
+            println!("{}", e);
+            process::exit(1);
+        })
+    });
+
+    let mode = match args.flag_mode {
+        Some(ref name) => {
+            Mode::parse(name).unwrap_or_else(|| {
+                println!("unknown mode '{}' (expected classic, time-attack or endless)", name);
+                process::exit(1);
+            })
+        }
+        None => Mode::Classic,
+    };
+
+    let time_limit = args.flag_time_limit.unwrap_or(120);
+    if time_limit <= 0 {
+        println!("time limit must be at least one second");
+        process::exit(1);
+    }
+
+    let lock_level = match args.flag_lock_level {
+        Some(ref name) => {
+            Some(LockLevel::parse(name).unwrap_or_else(|| {
+                println!("unknown lock level '{}' (expected very-easy, easy, average, hard or \
+                          very-hard)",
+                         name);
+                process::exit(1);
+            }))
+        }
+        None => None,
+    };
+
+    // Skill only comes into play if it or the lock level is set, so that ordinary games keep the
+    // usual board.
+    let skill = match (args.flag_science, lock_level) {
+        (None, None) => None,
+        (science, lock_level) => {
+            let science = science.unwrap_or(50);
+            if science < 0 || science > 100 {
+                println!("science skill must be between 0 and 100");
+                process::exit(1);
+            }
+            Some(Skill {
+                science: science,
+                lock_level: lock_level.unwrap_or(LockLevel::Average),
+            })
+        }
+    };
+
+    let script = args.flag_terminal.as_ref().map(|path| {
+        TerminalScript::load(Path::new(path)).unwrap_or_else(|e| {
+            println!("{}", e);
+            process::exit(1);
+        })
+    });
+
+    let mut session = Session {
+        difficulty: difficulty,
+        mode: mode,
+        time_limit: Duration::seconds(time_limit),
+        skill: skill,
+        effects: effects,
+        dictionary: config.dictionary.clone(),
+        record_path: args.flag_record.as_ref().map(PathBuf::from),
+    };
+
+    if args.cmd_host {
+        let port = args.flag_port.unwrap_or(telnet::DEFAULT_PORT);
+        let session = Session { record_path: None, ..session };
+        host(port, session, sound, theme, screenshot_format, config, catalog);
+        return;
+    }
+
+    // The accessible and headless modes don't take over the screen, so they have no frontend.
+    if args.flag_accessible {
+        if args.flag_terminal.is_some() || args.flag_replay.is_some() {
+            println!("--accessible can't be combined with --terminal or --replay");
+            process::exit(1);
+        }
+        accessible_games(&session);
+        return;
+    }
+    if args.flag_headless_json {
+        headless_games(&session);
+        return;
+    }
+
+    // Join the race before taking over the screen, so that waiting for it to start can be
+    // cancelled with Ctrl-C.
+    let race = if args.cmd_join {
+        let name = args.flag_name
+            .clone()
+            .or_else(|| env::var("USER").ok())
+            .unwrap_or_else(|| "Player".to_string());
+        let mut race = RaceClient::join(&args.arg_address, &name).unwrap_or_else(|e| {
+            println!("{}: {}", args.arg_address, e);
+            process::exit(1);
+        });
+        println!("Waiting for the race to start...");
+        let settings = race.wait_for_start().unwrap_or_else(|e| {
+            println!("{}: {}", args.arg_address, e);
+            process::exit(1);
+        });
+        Some((race, settings))
+    } else {
+        None
+    };
+
+    let mut frontend = Frontend {
+        window: window::create(&theme, effects, config.keys.clone(), config.markers),
+        sound: sound,
+        cast: cast,
+        theme: theme,
+        screenshot_format: screenshot_format,
+        race: None,
+        data_lock: Arc::new(Mutex::new(())),
+    };
+
+    if let Some((race, settings)) = race {
+        frontend.race = Some(race);
+        race_game(&mut frontend, &settings);
+        return;
+    }
+
+    let mut flags = Flags::new();
+    match (replay, script) {
+        (Some(replay), _) => {
+            info!("Watching replay with seed {}.", replay.settings.seed);
+            watch_replay(&mut frontend, replay, args.flag_speed.unwrap_or(1.0));
+        }
+        (None, Some(script)) => {
+            // A terminal's lock level always applies, even without a Science skill.
+            let skill = Skill {
+                science: args.flag_science.unwrap_or(50),
+                lock_level: script.lock_level,
+            };
+            hack_terminal(&mut frontend, &script, skill, &mut flags);
+        }
+        (None, None) => main_menu(&mut frontend, &mut session),
+    }
+
+    if let Some(ref path) = screenshot_path {
+        frontend.screenshot(Some(path));
+    }
+
+    // Report the flags a custom terminal set once the screen has been restored, so that scripts
+    // can act on them.
+    drop(frontend);
+    for flag in &flags {
+        println!("{}", flag);
+    }
+}
+
+fn print_stats() {
+    let records = StatsStore::open().and_then(|store| store.load()).unwrap_or_else(|e| {
+        println!("{}", i18n::message_with("error-statistics", &[&e]));
+        process::exit(1);
+    });
+    print!("{}", stats::summary(&records));
+}
+
+fn print_leaderboards(difficulty: Option<i32>) {
+    let leaderboards = match difficulty {
+        Some(difficulty) => Leaderboard::load(difficulty).map(|leaderboard| vec![leaderboard]),
+        None => Leaderboard::load_all(),
+    };
+    let leaderboards = leaderboards.unwrap_or_else(|e| {
+        println!("{}", i18n::message_with("error-leaderboard", &[&e]));
+        process::exit(1);
+    });
+
+    for line in leaderboard::summary(&leaderboards) {
+        println!("{}", line);
+    }
+}
+
+fn print_daily() {
+    let record = DailyStore::open()
+        .and_then(|store| store.find(&daily::today()))
+        .unwrap_or_else(|e| {
+            println!("{}", i18n::message_with("error-daily", &[&e]));
+            process::exit(1);
+        });
+
+    match record {
+        Some(record) => {
+            for line in record.share_lines() {
+                println!("{}", line);
+            }
+        }
+        None => println!("{}", i18n::message("daily-unfinished")),
+    }
+}
+
+/// Puts a won game on the leaderboard if it scored well enough, asking for the player's name.
+fn record_high_score(frontend: &mut Frontend, record: &GameRecord, attempts_left: i32) {
+    let score = leaderboard::score(record.difficulty,
+                                   attempts_left,
+                                   record.duration,
+                                   record.brackets_consumed);
+    match Leaderboard::load(record.difficulty) {
+        Ok(ref leaderboard) if leaderboard.qualifies(score) => (),
+        Ok(_) => return,
+        Err(e) => {
+            warn!("Could not read leaderboard: {}", e);
+            return;
+        }
+    }
+
+    let message = i18n::message_with("high-score", &[&score]);
+    let name = match frontend.prompt(&message, leaderboard::MAX_NAME_LENGTH) {
+        Some(name) => name,
+        None => return,
+    };
+
+    // Other sessions may have saved scores while the name was typed, so the leaderboard is read
+    // again before the score goes in.
+    let _data = frontend.lock_data();
+    let saved = Leaderboard::load(record.difficulty).and_then(|mut leaderboard| {
+        leaderboard.insert(leaderboard::new_score(score, &name, record.duration));
+        leaderboard.save()
+    });
+    match saved {
+        Ok(path) => info!("Saved high score to {}.", path.display()),
+        Err(e) => warn!("Could not save high score: {}", e),
+    }
+}
+
+/// Adds a finished game to the statistics.
+fn save_stats(tracker: &StatsTracker) {
+    if let Some(record) = tracker.record() {
+        match StatsStore::open() {
+            Ok(store) => {
+                match store.append(&record) {
+                    Ok(()) => info!("Saved game to {}.", store.path().display()),
+                    Err(e) => warn!("Could not save game to {}: {}", store.path().display(), e),
+                }
+            }
+            Err(e) => warn!("Could not open statistics: {}", e),
+        }
+    }
+}
+
+/// Everything that a game is shown and heard through.
+struct Frontend {
+    window: Box<Window>,
+    sound: SoundPlayer,
+    cast: Option<CastRecorder>,
+    theme: Theme,
+    screenshot_format: Format,
+
+    /// The race being played, if the player joined one.
+    race: Option<RaceClient>,
+
+    /// Held while the saved statistics, scores and results are changed, since every hosted
+    /// session shares them.
+    data_lock: Arc<Mutex<()>>,
+}
+
+impl Frontend {
+    /// Keeps other sessions from changing the saved data until the guard is dropped.
+    fn lock_data<'a>(&'a self) -> MutexGuard<'a, ()> {
+        // A session that panicked while holding the lock didn't leave anything half written,
+        // since the files are appended to or replaced whole.
+        self.data_lock.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Passes game events on to everything that is interested in them.
+    fn dispatch(&mut self, events: &[GameEvent]) {
+        for event in events {
+            debug!("{:?}", event);
+            self.window.notify(event);
+            if let Some(ref mut race) = self.race {
+                race.notify(event);
+            }
+        }
+        self.sound.notify_all(events);
+        if self.sound.take_bell() {
+            self.window.bell();
+        }
+    }
+
+    /// Draws the game, and records the frame if we're casting.
+    fn present(&mut self, game_state: &GameState) {
+        if let Some(ref mut race) = self.race {
+            if race.poll() {
+                self.window.set_panel(Some(race.scoreboard.panel()));
+            }
+        }
+        self.window.render(game_state);
+        self.record_frame();
+    }
+
+    fn present_menu(&mut self, menu: &Menu) {
+        self.window.render_menu(menu);
+        self.record_frame();
+    }
+
+    /// Asks the player to type a line of text, such as their name. Returns `None` if they
+    /// cancel.
+    fn prompt(&mut self, message: &str, max_length: usize) -> Option<String> {
+        let mut prompt = Prompt::new(max_length);
+        loop {
+            self.window.render_prompt(&prompt, message);
+            self.record_frame();
+            if let Some(key) = self.window.read_key() {
+                if let Some(answer) = prompt.handle(key) {
+                    return answer;
+                }
+            }
+        }
+    }
+
+    /// Shows a page of text, such as statistics, until the player leaves it.
+    fn show_page(&mut self, title: &str, lines: &[String]) {
+        let mut page = Page::new(title, lines);
+        loop {
+            self.window.render_page(&mut page);
+            self.record_frame();
+            if let Some(key) = self.window.read_key() {
+                if !page.handle(key) {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn record_frame(&mut self) {
+        let failed = match self.cast {
+            Some(ref mut recorder) => {
+                recorder.record(self.window.frame())
+                    .map_err(|e| warn!("Could not record frame, stopping cast: {}", e))
+                    .is_err()
+            }
+            None => false,
+        };
+        if failed {
+            self.cast = None;
+        }
+    }
+
+    /// Saves the last frame. If there's no path, a new file is created in the current directory.
+    /// Returns the path of the screenshot if it was saved.
+    fn screenshot(&self, path: Option<&Path>) -> Option<PathBuf> {
+        let (path, format) = match path {
+            Some(path) => {
+                (path.to_path_buf(), Format::from_path(path).unwrap_or(self.screenshot_format))
+            }
+            None => (screenshot::default_path(self.screenshot_format), self.screenshot_format),
+        };
+
+        match screenshot::save(&path, self.window.frame(), &self.theme, format) {
+            Ok(()) => {
+                info!("Saved screenshot to {}.", path.display());
+                Some(path)
+            }
+            Err(e) => {
+                warn!("Could not save screenshot to {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+}
+
+/// Everything the player can change between games.
+#[derive(Clone)]
+struct Session {
+    difficulty: i32,
+    mode: Mode,
+
+    /// How long each terminal can be worked on in time-attack mode.
+    time_limit: Duration,
+
+    skill: Option<Skill>,
+
+    effects: EffectSettings,
+
+    /// The file passwords are picked from, if not the system dictionary.
+    dictionary: Option<String>,
+
+    /// Where to record the next game, if it should be recorded.
+    record_path: Option<PathBuf>,
+}
+
+impl Session {
+    /// The settings for a new game in classic or time-attack mode.
+    fn game_settings(&self) -> GameSettings {
+        let settings = match self.mode {
+            Mode::TimeAttack => mode::time_attack(self.difficulty, self.time_limit),
+            _ => GameSettings::new(self.difficulty),
+        };
+        self.apply(settings)
+    }
+
+    /// Applies the Science skill and the dictionary to a game's settings.
+    fn apply(&self, settings: GameSettings) -> GameSettings {
+        let settings = GameSettings { dictionary: self.dictionary.clone(), ..settings };
+        match self.skill {
+            Some(skill) => skill.apply(settings),
+            None => settings,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum MainMenuItem {
+    NewGame,
+    Daily,
+    Mode,
+    Difficulty,
+    Statistics,
+    HighScores,
+    Options,
+    Quit,
+}
+
+const MAIN_MENU: [MainMenuItem; 8] = [MainMenuItem::NewGame,
+                                      MainMenuItem::Daily,
+                                      MainMenuItem::Mode,
+                                      MainMenuItem::Difficulty,
+                                      MainMenuItem::Statistics,
+                                      MainMenuItem::HighScores,
+                                      MainMenuItem::Options,
+                                      MainMenuItem::Quit];
+
+#[derive(Debug, Clone, Copy)]
+enum OptionsItem {
+    Sound,
+    Effects,
+    ReducedMotion,
+    Back,
+}
+
+const OPTIONS_MENU: [OptionsItem; 4] = [OptionsItem::Sound,
+                                        OptionsItem::Effects,
+                                        OptionsItem::ReducedMotion,
+                                        OptionsItem::Back];
+
+fn on_off(value: bool) -> String {
+    i18n::message(if value { "on" } else { "off" })
+}
+
+/// Hosts a race on a board of words picked from the dictionary.
+fn serve_race(dictionary: &str, difficulty: i32, port: u16, players: usize) {
+    let words = words::load(Path::new(dictionary), difficulty as usize).unwrap_or_else(|e| {
+        println!("{}: {}", dictionary, e);
+        process::exit(1);
+    });
+    if words.len() < NUM_WORDS as usize {
+        println!("{} doesn't have enough words of {} letters for a race", dictionary, difficulty);
+        process::exit(1);
+    }
+    let board = rand::sample(&mut rand::thread_rng(), words.into_iter(), NUM_WORDS as usize);
+
+    if let Err(e) = race::serve(port, players, board) {
+        println!("Could not host the race: {}", e);
+        process::exit(1);
+    }
+}
+
+/// Serves the game to every terminal that connects over telnet, each starting from the same
+/// session and sound settings.
+fn host(port: u16,
+        session: Session,
+        sound: SoundPlayer,
+        theme: Theme,
+        screenshot_format: Format,
+        config: Config,
+        catalog: Catalog) {
+    let data_lock = Arc::new(Mutex::new(()));
+    let result = telnet::serve(port, move |stream| {
+        // Every thread starts out in English.
+        i18n::set_catalog(catalog.clone());
+
+        let window = TelnetWindow::new(stream,
+                                       &theme,
+                                       session.effects,
+                                       config.keys.clone(),
+                                       config.markers);
+        let window = match window {
+            Ok(window) => window,
+            Err(e) => {
+                warn!("Could not set up telnet session: {}", e);
+                return;
+            }
+        };
+        let mut frontend = Frontend {
+            window: Box::new(window),
+            sound: sound.clone(),
+            cast: None,
+            theme: theme.clone(),
+            screenshot_format: screenshot_format,
+            race: None,
+            data_lock: data_lock.clone(),
+        };
+        main_menu(&mut frontend, &mut session.clone());
+    });
+
+    if let Err(e) = result {
+        println!("Could not serve the game: {}", e);
+        process::exit(1);
+    }
+}
+
+/// Shows the main menu until the player quits.
+fn main_menu(frontend: &mut Frontend, session: &mut Session) {
+    let mut menu = Menu::new(&i18n::message("menu-main"), vec![]);
+
+    let mut last_time = PreciseTime::now();
+    loop {
+        let elapsed = last_time.to(PreciseTime::now());
+        last_time = PreciseTime::now();
+
+        menu.items = MAIN_MENU.iter()
+            .map(|item| {
+                match *item {
+                    MainMenuItem::NewGame => i18n::message("menu-new-game"),
+                    MainMenuItem::Daily => i18n::message("menu-daily"),
+                    MainMenuItem::Mode => i18n::message_with("menu-mode", &[&session.mode.name()]),
+                    MainMenuItem::Difficulty => {
+                        i18n::message_with("menu-difficulty", &[&session.difficulty])
+                    }
+                    MainMenuItem::Statistics => i18n::message("menu-statistics"),
+                    MainMenuItem::HighScores => i18n::message("menu-high-scores"),
+                    MainMenuItem::Options => i18n::message("menu-options"),
+                    MainMenuItem::Quit => i18n::message("menu-quit"),
+                }
+            })
+            .collect();
+        frontend.window.update(elapsed);
+        frontend.present_menu(&menu);
+
+        let choice = frontend.window.handle_menu_input().and_then(|input| menu.handle(input));
+        let item = match choice {
+            Some(MenuChoice::Item(index)) => MAIN_MENU[index],
+            Some(MenuChoice::Back) => return,
+            None => continue,
+        };
+
+        match item {
+            MainMenuItem::NewGame => {
+                if let Some(skill) = session.skill {
+                    if !skill.can_hack() {
+                        let title = i18n::message("page-terminal-locked");
+                        frontend.show_page(&title, &skill.refusal());
+                        continue;
+                    }
+                }
+                match session.mode {
+                    Mode::Classic | Mode::TimeAttack => new_game(frontend, session),
+                    Mode::Endless => endless_run(frontend, session),
+                }
+            }
+            MainMenuItem::Daily => daily_game(frontend),
+            MainMenuItem::Mode => session.mode = session.mode.next(),
+            MainMenuItem::Difficulty => {
+                session.difficulty = if session.difficulty >= MAX_DIFFICULTY {
+                    MIN_DIFFICULTY
+                } else {
+                    session.difficulty + 1
+                };
+            }
+            MainMenuItem::Statistics => {
+                let lines = match StatsStore::open().and_then(|store| store.load()) {
+                    Ok(records) => stats::summary(&records).lines().map(String::from).collect(),
+                    Err(e) => vec![i18n::message_with("error-statistics", &[&e])],
+                };
+                frontend.show_page(&i18n::message("menu-statistics"), &lines);
+            }
+            MainMenuItem::HighScores => {
+                let lines = match Leaderboard::load_all() {
+                    Ok(leaderboards) => leaderboard::summary(&leaderboards),
+                    Err(e) => vec![i18n::message_with("error-leaderboard", &[&e])],
+                };
+                frontend.show_page(&i18n::message("menu-high-scores"), &lines);
+            }
+            MainMenuItem::Options => options_menu(frontend, session),
+            MainMenuItem::Quit => return,
+        }
+        last_time = PreciseTime::now();
+    }
+}
+
+fn options_menu(frontend: &mut Frontend, session: &mut Session) {
+    let mut menu = Menu::new(&i18n::message("menu-options"), vec![]);
+
+    let mut last_time = PreciseTime::now();
+    loop {
+        let elapsed = last_time.to(PreciseTime::now());
+        last_time = PreciseTime::now();
+
+        menu.items = OPTIONS_MENU.iter()
+            .map(|item| {
+                match *item {
+                    OptionsItem::Sound => {
+                        i18n::message_with("menu-sound", &[&on_off(!frontend.sound.muted())])
+                    }
+                    OptionsItem::Effects => {
+                        i18n::message_with("menu-effects", &[&on_off(session.effects.enabled)])
+                    }
+                    OptionsItem::ReducedMotion => {
+                        i18n::message_with("menu-reduced-motion",
+                                           &[&on_off(session.effects.reduced_motion)])
+                    }
+                    OptionsItem::Back => i18n::message("menu-back"),
+                }
+            })
+            .collect();
+        frontend.window.update(elapsed);
+        frontend.present_menu(&menu);
+
+        let choice = frontend.window.handle_menu_input().and_then(|input| menu.handle(input));
+        let item = match choice {
+            Some(MenuChoice::Item(index)) => OPTIONS_MENU[index],
+            Some(MenuChoice::Back) => return,
+            None => continue,
+        };
+
+        match item {
+            OptionsItem::Sound => frontend.sound.toggle_mute(),
+            OptionsItem::Effects => {
+                session.effects.enabled = !session.effects.enabled;
+                frontend.window.set_effects(session.effects);
+            }
+            OptionsItem::ReducedMotion => {
+                session.effects.reduced_motion = !session.effects.reduced_motion;
+                frontend.window.set_effects(session.effects);
+            }
+            OptionsItem::Back => return,
+        }
+    }
+}
+
+/// How a round of the game went.
+struct Round {
+    /// How the game ended, or `None` if the player left before it did.
+    ending: Option<GameEnding>,
+    attempts_left: i32,
+
+    /// Whether the game was won by getting around the password rather than guessing it.
+    bypassed: bool,
+
+    /// Whether the player asked for another round rather than going back to the menu.
+    play_again: bool,
+}
+
+/// Starts recording a game, if the session should still be recorded.
+fn start_recording(session: &mut Session, settings: &GameSettings) -> Option<Recorder> {
+    session.record_path.take().and_then(|path| {
+        Recorder::create(&path, settings)
+            .map_err(|e| warn!("Could not record game to {}: {}", path.display(), e))
+            .ok()
+    })
+}
+
+/// Plays rounds at the session's difficulty until the player goes back to the menu.
+fn new_game(frontend: &mut Frontend, session: &mut Session) {
+    loop {
+        let settings = session.game_settings();
+        let recorder = start_recording(session, &settings);
+
+        info!("Starting {} game with seed {}.", session.mode.name(), settings.seed);
+        frontend.window.set_status(None);
+        if !play(frontend, &settings, recorder, &mut []).play_again {
+            return;
+        }
+    }
+}
+
+/// Plays games in plain text until the player stops. Endless runs aren't available, so that
+/// mode plays classic games instead.
+fn accessible_games(session: &Session) {
+    if let Some(skill) = session.skill {
+        if !skill.can_hack() {
+            for line in skill.refusal() {
+                println!("{}", line);
+            }
+            return;
+        }
+    }
+
+    loop {
+        let settings = session.game_settings();
+        let mut tracker = StatsTracker::new(&settings);
+
+        info!("Starting accessible game with seed {}.", settings.seed);
+        let play_again = accessible::play(&settings, &mut tracker);
+        save_stats(&tracker);
+        if !play_again {
+            return;
+        }
+    }
+}
+
+/// Plays games driven by another program until it stops. They don't count towards the
+/// statistics, so that a bot playing thousands of games doesn't drown out the player's own.
+fn headless_games(session: &Session) {
+    if let Some(skill) = session.skill {
+        if !skill.can_hack() {
+            headless::refuse(&skill.refusal());
+            return;
+        }
+    }
+
+    loop {
+        let settings = session.game_settings();
+        info!("Starting headless game with seed {}.", settings.seed);
+        if !headless::play(&settings) {
+            return;
+        }
+    }
+}
+
+/// Races the other players to crack a terminal, then shows the results.
+fn race_game(frontend: &mut Frontend, settings: &GameSettings) {
+    info!("Racing on a board of {} words with seed {}.",
+          settings.word_pool.as_ref().map_or(0, Vec::len),
+          settings.seed);
+    if let Some(ref race) = frontend.race {
+        frontend.window.set_panel(Some(race.scoreboard.panel()));
+    }
+
+    play(frontend, settings, None, &mut []);
+
+    frontend.window.set_panel(None);
+    let lines = frontend.race.as_ref().map_or(vec![], |race| race.scoreboard.lines());
+    frontend.show_page(&i18n::message("page-race"), &lines);
+}
+
+/// Plays terminals of increasing difficulty until one locks or the player leaves.
+fn endless_run(frontend: &mut Frontend, session: &mut Session) {
+    let mut run = EndlessRun::new(session.difficulty);
+    loop {
+        let settings = session.apply(run.settings());
+        let recorder = start_recording(session, &settings);
+
+        info!("Starting terminal {} of endless run with seed {}.",
+              run.cracked + 1,
+              settings.seed);
+        let status = i18n::message_with("endless-status", &[&run.cracked]);
+        frontend.window.set_status(Some(status));
+
+        let round = play(frontend, &settings, recorder, &mut []);
+        let cracked = round.ending == Some(GameEnding::Won) && !round.bypassed;
+        if cracked {
+            run.advance(round.attempts_left);
+        }
+        if !cracked || !round.play_again {
+            break;
+        }
+    }
+
+    let saved = {
+        let _data = frontend.lock_data();
+        run.save()
+    };
+    if let Err(e) = saved {
+        warn!("Could not save endless run: {}", e);
+    }
+    let mut lines = vec![i18n::message_with("endless-cracked", &[&run.cracked])];
+    match mode::best_run() {
+        Ok(best) => lines.push(i18n::message_with("endless-best", &[&best])),
+        Err(e) => warn!("Could not read endless runs: {}", e),
+    }
+    frontend.show_page(&i18n::message("page-endless"), &lines);
+}
+
+/// Plays today's daily terminal, or shows how it went if it has already been played.
+fn daily_game(frontend: &mut Frontend) {
+    let date = daily::today();
+    let store = match DailyStore::open() {
+        Ok(store) => store,
+        Err(e) => {
+            let lines = vec![i18n::message_with("error-daily", &[&e])];
+            frontend.show_page(&i18n::message("menu-daily"), &lines);
+            return;
+        }
+    };
+
+    let record = match store.find(&date) {
+        Ok(Some(record)) => Some(record),
+        Ok(None) => {
+            let settings = daily::settings(&date);
+            let mut tracker = DailyTracker::new(&date, &settings);
+
+            info!("Starting daily terminal for {}.", date);
+            frontend.window.set_status(None);
+            play(frontend, &settings, None, &mut [&mut tracker]);
+            tracker.abandon();
+
+            let record = tracker.record();
+            if let Some(ref record) = record {
+                // Only the first result of the day counts, even if another session finished
+                // the same terminal while this one was being played.
+                let _data = frontend.lock_data();
+                let saved = match store.find(&date) {
+                    Ok(Some(_)) => Ok(()),
+                    Ok(None) => store.append(record),
+                    Err(e) => Err(e),
+                };
+                match saved {
+                    Ok(()) => info!("Saved daily result to {}.", store.path().display()),
+                    Err(e) => warn!("Could not save daily result: {}", e),
+                }
+            }
+            record
+        }
+        Err(e) => {
+            warn!("Could not read daily results: {}", e);
+            None
+        }
+    };
+
+    // The results couldn't be read, so there's nothing to show.
+    let mut lines = match record {
+        Some(record) => record.share_lines(),
+        None => return,
+    };
+    lines.push(String::new());
+    lines.push(i18n::message("daily-tomorrow"));
+    lines.push(i18n::message("daily-share"));
+    frontend.show_page(&i18n::message("menu-daily"), &lines);
+}
+
+/// Hacks a custom terminal, then lets the player use its menus until they log out.
+fn hack_terminal(frontend: &mut Frontend,
+                 script: &TerminalScript,
+                 skill: Skill,
+                 flags: &mut Flags) {
+    if !skill.can_hack() {
+        frontend.show_page(&i18n::message("page-terminal-locked"), &skill.refusal());
+        return;
+    }
+
+    loop {
+        let settings = skill.apply(script.settings());
+        info!("Hacking custom terminal with seed {}.", settings.seed);
+        frontend.window.set_status(None);
+
+        let round = play(frontend, &settings, None, &mut []);
+        match round.ending {
+            Some(GameEnding::Won) => break,
+            Some(GameEnding::Lost) if round.play_again => (),
+            _ => return,
+        }
+    }
+
+    frontend.window.set_status(None);
+    terminal_menu(frontend, script, "", &script.menu, flags);
+}
+
+/// Shows one level of a custom terminal's menu tree.
+fn terminal_menu(frontend: &mut Frontend,
+                 script: &TerminalScript,
+                 title: &str,
+                 entries: &[MenuEntry],
+                 flags: &mut Flags) {
+    let mut menu = Menu::new(title, vec![]);
+    menu.header = script.header.clone();
 
     let mut last_time = PreciseTime::now();
-    while game_state.is_playing {
+    loop {
         let elapsed = last_time.to(PreciseTime::now());
-        let event = window.handle_input(&mut game_state);
-        match event {
-            Some(InputEvent::Quit) => break,
-            _ => game_state.update(event, elapsed),
+        last_time = PreciseTime::now();
+
+        let visible = entries.iter().filter(|entry| entry.visible(flags)).collect::<Vec<_>>();
+        menu.items = visible.iter().map(|entry| entry.label.clone()).collect();
+
+        // An action may have hidden the entry below the selection.
+        menu.selected = cmp::min(menu.selected, menu.items.len().saturating_sub(1));
+        frontend.window.update(elapsed);
+        frontend.present_menu(&menu);
+
+        let choice = frontend.window.handle_menu_input().and_then(|input| menu.handle(input));
+        let entry = match choice {
+            Some(MenuChoice::Item(index)) if index < visible.len() => visible[index],
+            Some(MenuChoice::Back) => return,
+            _ => continue,
+        };
+
+        match entry.content {
+            Content::Page(ref lines) => frontend.show_page(&entry.label, lines),
+            Content::Menu(ref entries) => {
+                terminal_menu(frontend, script, &entry.label, entries, flags)
+            }
+            Content::Action { ref set, ref clear, ref message } => {
+                for flag in set {
+                    info!("Setting flag '{}'.", flag);
+                    flags.insert(flag.clone());
+                }
+                for flag in clear {
+                    info!("Clearing flag '{}'.", flag);
+                    flags.remove(flag);
+                }
+                if !message.is_empty() {
+                    frontend.show_page(&entry.label, message);
+                }
+            }
         }
-        window.render(&game_state);
         last_time = PreciseTime::now();
     }
 }
+
+/// Plays one game. Besides the statistics, every event is passed to `observers`.
+fn play(frontend: &mut Frontend,
+        settings: &GameSettings,
+        mut recorder: Option<Recorder>,
+        observers: &mut [&mut Subscriber])
+        -> Round {
+    let mut game_state = match GameState::new(settings) {
+        Ok(game_state) => game_state,
+        Err(e) => {
+            warn!("Could not start game: {}", e);
+            frontend.show_page(&i18n::message("page-no-game"), &[e]);
+            return Round {
+                ending: None,
+                attempts_left: 0,
+                bypassed: false,
+                play_again: false,
+            };
+        }
+    };
+    let mut tracker = StatsTracker::new(settings);
+
+    let mut stopwatch = Stopwatch::start();
+    loop {
+        let mut elapsed = stopwatch.lap();
+        let event = frontend.window.handle_input(&game_state);
+
+        // The clock keeps running while the log is open.
+        if let Some(InputEvent::ShowLog) = event {
+            frontend.show_page(&i18n::message("page-log"), &game_state.log());
+            elapsed = elapsed + stopwatch.lap();
+        }
+
+        let events = match event {
+            Some(InputEvent::Quit) if game_state.command_line.is_none() => {
+                return Round {
+                    ending: game_state.status,
+                    attempts_left: game_state.attempts,
+                    bypassed: game_state.bypassed,
+                    play_again: false,
+                }
+            }
+            Some(InputEvent::Action) if game_state.status.is_some() => {
+                return Round {
+                    ending: game_state.status,
+                    attempts_left: game_state.attempts,
+                    bypassed: game_state.bypassed,
+                    play_again: true,
+                }
+            }
+            Some(InputEvent::ToggleMute) => {
+                frontend.sound.toggle_mute();
+                vec![]
+            }
+            Some(InputEvent::Screenshot) => {
+                if let Some(path) = frontend.screenshot(None) {
+                    let status = i18n::message_with("screenshot-saved", &[&path.display()]);
+                    frontend.window.set_status(Some(status));
+                }
+                vec![]
+            }
+            _ => {
+                let failed = match recorder {
+                    Some(ref mut recorder) => {
+                        recorder.record(event.as_ref(), elapsed)
+                            .map_err(|e| warn!("Could not record input, stopping recording: {}", e))
+                            .is_err()
+                    }
+                    None => false,
+                };
+                if failed {
+                    recorder = None;
+                }
+                game_state.update(event, elapsed)
+            }
+        };
+
+        let was_finished = tracker.finished();
+        tracker.update(elapsed);
+        for event in &events {
+            tracker.notify(event);
+            for observer in observers.iter_mut() {
+                observer.notify(event);
+            }
+        }
+        if tracker.finished() && !was_finished {
+            {
+                let _data = frontend.lock_data();
+                save_stats(&tracker);
+            }
+            if let Some(record) = tracker.record() {
+                if record.result == GameEnding::Won {
+                    record_high_score(frontend, &record, game_state.attempts);
+                }
+            }
+        }
+
+        frontend.dispatch(&events);
+        frontend.window.update(elapsed);
+        frontend.present(&game_state);
+    }
+}
+
+fn watch_replay(frontend: &mut Frontend, replay: Replay, mut speed: f64) {
+    let mut game_state = match GameState::new(&replay.settings) {
+        Ok(game_state) => game_state,
+        Err(e) => {
+            frontend.show_page(&i18n::message("page-no-game"), &[e]);
+            return;
+        }
+    };
+    let mut inputs = replay.events.into_iter().peekable();
+
+    // How far into the recording we are, and how far the game has been updated to, in
+    // milliseconds.
+    let mut clock = 0.0;
+    let mut game_clock = 0;
+    let mut paused = false;
+
+    let mut last_time = PreciseTime::now();
+    loop {
+        let elapsed = last_time.to(PreciseTime::now());
+        last_time = PreciseTime::now();
+
+        match frontend.window.handle_replay_input() {
+            Some(ReplayControl::Quit) => break,
+            Some(ReplayControl::TogglePause) => paused = !paused,
+            Some(ReplayControl::Step) => {
+                paused = true;
+                if let Some(&(time, _)) = inputs.peek() {
+                    clock = time as f64;
+                }
+            }
+            Some(ReplayControl::Faster) => speed = (speed * 2.0).min(MAX_REPLAY_SPEED),
+            Some(ReplayControl::Slower) => speed = (speed / 2.0).max(MIN_REPLAY_SPEED),
+            Some(ReplayControl::Screenshot) => {
+                frontend.screenshot(None);
+            }
+            None => (),
+        }
+
+        if !paused {
+            clock += elapsed.num_microseconds().unwrap_or(0) as f64 / 1000.0 * speed;
+        }
+
+        while inputs.peek().map_or(false, |&(time, _)| time as f64 <= clock) {
+            let (time, input) = inputs.next().unwrap();
+            let events = game_state.update(Some(input), Duration::milliseconds(time - game_clock));
+            game_clock = time;
+            frontend.dispatch(&events);
+        }
+
+        if (clock as i64) > game_clock {
+            let events = game_state.update(None, Duration::milliseconds(clock as i64 - game_clock));
+            game_clock = clock as i64;
+            frontend.dispatch(&events);
+        }
+
+        let state = if inputs.peek().is_none() {
+            i18n::message("replay-end")
+        } else if paused {
+            i18n::message("replay-paused")
+        } else {
+            format!("{}x", speed)
+        };
+        let status = i18n::message_with("replay-status", &[&state]);
+        frontend.window.set_status(Some(status));
+
+        frontend.window.update(elapsed);
+        frontend.present(&game_state);
+    }
+}
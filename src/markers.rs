@@ -0,0 +1,62 @@
+/// How the word or bracket pair under the cursor stands out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightStyle {
+    /// The theme's highlight colors, or reverse video without colors.
+    Reverse,
+    Underline,
+    Bold,
+}
+
+pub const HIGHLIGHT_STYLES: [HighlightStyle; 3] = [HighlightStyle::Reverse,
+                                                   HighlightStyle::Underline,
+                                                   HighlightStyle::Bold];
+
+impl HighlightStyle {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            HighlightStyle::Reverse => "reverse",
+            HighlightStyle::Underline => "underline",
+            HighlightStyle::Bold => "bold",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<HighlightStyle> {
+        HIGHLIGHT_STYLES.iter().cloned().find(|style| style.name() == name)
+    }
+}
+
+/// How things are marked on the board, for players who find the defaults hard to tell apart.
+#[derive(Debug, Clone, Copy)]
+pub struct Markers {
+    pub highlight: HighlightStyle,
+
+    /// Drawn over words that have been guessed.
+    pub guessed: char,
+
+    /// Drawn over words that a bracket pair removed as duds.
+    pub removed: char,
+
+    /// Whether guessed words show their likeness to the password.
+    pub inline_likeness: bool,
+}
+
+impl Default for Markers {
+    fn default() -> Markers {
+        Markers {
+            highlight: HighlightStyle::Reverse,
+            // Neither is a letter, and the tilde isn't among the garbage characters either.
+            guessed: '~',
+            removed: '.',
+            inline_likeness: false,
+        }
+    }
+}
+
+/// Reads a marker, which must be a single visible character.
+pub fn parse_marker(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_whitespace() && !c.is_control() => Some(c),
+        _ => None,
+    }
+}
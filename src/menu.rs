@@ -0,0 +1,87 @@
+use std::cmp;
+
+use ::consts::*;
+use ::i18n;
+use ::screen::Screen;
+
+/// Controls available while a menu is shown.
+pub enum MenuInput {
+    Up,
+    Down,
+    Select,
+    Back,
+}
+
+/// What the player chose from a menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuChoice {
+    Item(usize),
+    Back,
+}
+
+/// A list of options, one of which is selected, shown in the style of a RobCo terminal.
+pub struct Menu {
+    /// Lines shown above the title.
+    pub header: Vec<String>,
+    pub title: String,
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+
+impl Menu {
+    pub fn new(title: &str, items: Vec<String>) -> Menu {
+        Menu {
+            // Terminals can replace the header with their own.
+            header: vec![i18n::message("header")],
+            title: title.to_string(),
+            items: items,
+            selected: 0,
+        }
+    }
+
+    /// Moves the selection, returning the player's choice if they made one.
+    pub fn handle(&mut self, input: MenuInput) -> Option<MenuChoice> {
+        match input {
+            MenuInput::Up => {
+                if self.selected > 0 {
+                    self.selected -= 1;
+                }
+                None
+            }
+            MenuInput::Down => {
+                if self.selected + 1 < self.items.len() {
+                    self.selected += 1;
+                }
+                None
+            }
+            MenuInput::Select => Some(MenuChoice::Item(self.selected)),
+            MenuInput::Back => Some(MenuChoice::Back),
+        }
+    }
+}
+
+/// Draws a menu into a screen buffer, highlighting the selected item.
+pub fn draw(screen: &mut Screen, menu: &Menu) {
+    for (i, line) in menu.header.iter().enumerate() {
+        screen.print(MARGIN + i as i32, MARGIN, line);
+    }
+    let title_row = MARGIN + menu.header.len() as i32;
+    screen.print(title_row, MARGIN, &menu.title.to_uppercase());
+
+    let first_row = cmp::max(COLUMN_START_ROW, title_row + 2);
+    for (i, item) in menu.items.iter().enumerate() {
+        let row = first_row + i as i32;
+        let text = format!("> {}", item.to_uppercase());
+        screen.print(row, MARGIN, &text);
+        if i == menu.selected {
+            for col in MARGIN..MARGIN + text.chars().count() as i32 {
+                if let Some(cell) = screen.cell_mut(row, col) {
+                    cell.style.highlight = true;
+                }
+            }
+        }
+    }
+
+    let bottom = screen.height() - 1;
+    screen.print(bottom, 0, &i18n::message("hint-menu"));
+}
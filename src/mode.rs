@@ -0,0 +1,119 @@
+use std::cmp;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader};
+use std::io::prelude::*;
+
+use ::time::{self, Duration};
+
+use ::consts::*;
+use ::game::GameSettings;
+use ::paths;
+
+/// The name of the file that endless runs are kept in, in the data directory.
+const RUNS_FILE: &'static str = "endless.tsv";
+
+/// The ways a session can be played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// One untimed terminal after another.
+    Classic,
+
+    /// Each terminal locks if it isn't cracked before the time runs out.
+    TimeAttack,
+
+    /// A chain of terminals that get harder as they go, until one locks.
+    Endless,
+}
+
+pub const MODES: [Mode; 3] = [Mode::Classic, Mode::TimeAttack, Mode::Endless];
+
+impl Mode {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Mode::Classic => "classic",
+            Mode::TimeAttack => "time-attack",
+            Mode::Endless => "endless",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Mode> {
+        MODES.iter().cloned().find(|mode| mode.name() == name)
+    }
+
+    /// The mode after this one, for cycling through them on the menu.
+    pub fn next(&self) -> Mode {
+        let index = MODES.iter().position(|mode| mode == self).unwrap();
+        MODES[(index + 1) % MODES.len()]
+    }
+}
+
+/// Creates the settings for a time-attack game.
+pub fn time_attack(difficulty: i32, time_limit: Duration) -> GameSettings {
+    GameSettings { time_limit: Some(time_limit), ..GameSettings::new(difficulty) }
+}
+
+/// An endless run in progress. Each terminal is one level harder than the last, and starts with
+/// the attempts that were left on the last one.
+pub struct EndlessRun {
+    pub start_difficulty: i32,
+    pub difficulty: i32,
+    pub attempts: i32,
+
+    /// How many terminals have been cracked so far.
+    pub cracked: i32,
+}
+
+impl EndlessRun {
+    pub fn new(difficulty: i32) -> EndlessRun {
+        EndlessRun {
+            start_difficulty: difficulty,
+            difficulty: difficulty,
+            attempts: STARTING_ATTEMPTS,
+            cracked: 0,
+        }
+    }
+
+    /// The settings for the next terminal in the run.
+    pub fn settings(&self) -> GameSettings {
+        GameSettings { attempts: self.attempts, ..GameSettings::new(self.difficulty) }
+    }
+
+    /// Moves on after cracking a terminal with `attempts_left`. The guess that cracked it is
+    /// given back, so the next terminal always has at least one attempt.
+    pub fn advance(&mut self, attempts_left: i32) {
+        self.cracked += 1;
+        self.difficulty = cmp::min(self.difficulty + 1, MAX_DIFFICULTY);
+        self.attempts = cmp::min(attempts_left + 1, STARTING_ATTEMPTS);
+    }
+
+    /// Adds the finished run to the history in the data directory.
+    pub fn save(&self) -> io::Result<()> {
+        let path = paths::data_file(RUNS_FILE)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file,
+                 "{}\t{}\t{}",
+                 time::get_time().sec,
+                 self.start_difficulty,
+                 self.cracked)
+    }
+}
+
+/// The most terminals cracked in any endless run.
+pub fn best_run() -> io::Result<i32> {
+    let path = paths::data_file(RUNS_FILE)?;
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut best = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        match line.split('\t').nth(2).and_then(|cracked| cracked.parse().ok()) {
+            Some(cracked) => best = cmp::max(best, cracked),
+            None => warn!("{}: skipping invalid run '{}'", path.display(), line),
+        }
+    }
+    Ok(best)
+}
@@ -0,0 +1,96 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// The directory that saved data (statistics, scores) is kept in, following the XDG base
+/// directory specification: `$XDG_DATA_HOME/robco-term`, or `~/.local/share/robco-term`.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_DATA_HOME") {
+        Some(ref dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            match env::home_dir() {
+                Some(home) => home.join(".local").join("share"),
+                None => return None,
+            }
+        }
+    };
+    Some(base.join("robco-term"))
+}
+
+/// The directory that the configuration file is kept in: `$XDG_CONFIG_HOME/robco-term`, or
+/// `~/.config/robco-term`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(ref dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            match env::home_dir() {
+                Some(home) => home.join(".config"),
+                None => return None,
+            }
+        }
+    };
+    Some(base.join("robco-term"))
+}
+
+/// Returns the path of a file in the data directory, creating the directory if necessary.
+pub fn data_file(name: &str) -> io::Result<PathBuf> {
+    let dir = data_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not find home directory"))?;
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(name))
+}
+
+/// Replaces a file with what `write` writes to it. The new contents go to a temporary file next
+/// to it first, which is then renamed over it, so that anyone reading the file sees either all of
+/// the old contents or all of the new.
+pub fn replace_file<F>(path: &Path, write: F) -> io::Result<()>
+    where F: FnOnce(&mut BufWriter<File>) -> io::Result<()>
+{
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", process::id()));
+    let temporary = PathBuf::from(temporary);
+
+    let result = File::create(&temporary).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()
+    });
+    match result.and_then(|()| fs::rename(&temporary, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&temporary);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::{self, Read, Write};
+
+    use super::replace_file;
+
+    #[test]
+    fn replacing_a_file_leaves_no_temporary_file_behind() {
+        let dir = env::temp_dir().join(format!("robco-term-paths-{}", ::std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scores.tsv");
+        File::create(&path).unwrap().write_all(b"old\n").unwrap();
+
+        replace_file(&path, |writer| writeln!(writer, "new")).unwrap();
+        let failed = replace_file(&path, |_| Err(io::Error::new(io::ErrorKind::Other, "full")));
+
+        let mut contents = String::new();
+        File::open(&path).unwrap().read_to_string(&mut contents).unwrap();
+        assert!(failed.is_err());
+        assert_eq!(contents, "new\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,608 @@
+use std::io::{self, BufReader};
+use std::io::prelude::*;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration as StdDuration;
+
+use ::rand::{self, Rng};
+use ::time::PreciseTime;
+
+use ::consts::{MAX_DIFFICULTY, MIN_DIFFICULTY};
+use ::game::{GameEvent, GameSettings, Subscriber};
+use ::i18n;
+use ::words;
+
+/// The port races are served on unless another is given.
+pub const DEFAULT_PORT: u16 = 7777;
+
+/// Names longer than this are cut short.
+const MAX_NAME_LENGTH: usize = 12;
+
+/// How long a connection has to say who's joining before it's dropped, so that it can't keep
+/// everyone else from joining.
+const JOIN_TIMEOUT_SECS: u64 = 10;
+
+/// How a racer is getting on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    /// Still racing, having guessed this many words.
+    Attempts(i32),
+
+    /// Cracked the terminal, this many milliseconds after the start.
+    Solved(i64),
+
+    Locked,
+
+    /// Disconnected before finishing.
+    Left,
+}
+
+impl Progress {
+    fn finished(&self) -> bool {
+        match *self {
+            Progress::Attempts(..) => false,
+            _ => true,
+        }
+    }
+
+    /// Orders racers: fastest solves first, then those still racing, then those who didn't make
+    /// it. Racers that rank the same stay in the order they joined.
+    fn rank(&self) -> (i32, i64) {
+        match *self {
+            Progress::Solved(milliseconds) => (0, milliseconds),
+            Progress::Attempts(..) => (1, 0),
+            Progress::Locked => (2, 0),
+            Progress::Left => (3, 0),
+        }
+    }
+}
+
+/// A line sent by a player to the server.
+///
+/// The protocol is plain text, one message per line, so it can be tried out with `nc`. A player
+/// starts with `join NAME`, then reports `attempts N` after each guess and `solved` or `locked`
+/// when their terminal opens or locks. Leaving is done by disconnecting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    Join(String),
+    Attempts(i32),
+    Solved,
+    Locked,
+}
+
+impl Request {
+    pub fn format(&self) -> String {
+        match *self {
+            Request::Join(ref name) => format!("join {}", name),
+            Request::Attempts(attempts) => format!("attempts {}", attempts),
+            Request::Solved => "solved".to_string(),
+            Request::Locked => "locked".to_string(),
+        }
+    }
+
+    pub fn parse(line: &str) -> Option<Request> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        match (words.first().cloned(), words.len()) {
+            (Some("join"), n) if n > 1 => Some(Request::Join(words[1..].join(" "))),
+            (Some("attempts"), 2) => words[1].parse().ok().map(Request::Attempts),
+            (Some("solved"), 1) => Some(Request::Solved),
+            (Some("locked"), 1) => Some(Request::Locked),
+            _ => None,
+        }
+    }
+}
+
+/// A line sent by the server to every player.
+///
+/// Players are numbered in the order they joined. Each is told its own number with `you ID`, and
+/// about every player with `player ID NAME`. The race begins with `start SEED WORD...`, giving
+/// the words on the board so that everyone's board is the same whatever their dictionary. After
+/// that, `attempts ID N`, `solved ID MILLISECONDS`, `locked ID` and `left ID` report each
+/// player's progress.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Update {
+    You(usize),
+    Player(usize, String),
+    Start(u64, Vec<String>),
+    Progress(usize, Progress),
+}
+
+impl Update {
+    pub fn format(&self) -> String {
+        match *self {
+            Update::You(id) => format!("you {}", id),
+            Update::Player(id, ref name) => format!("player {} {}", id, name),
+            Update::Start(seed, ref words) => format!("start {} {}", seed, words.join(" ")),
+            Update::Progress(id, Progress::Attempts(attempts)) => {
+                format!("attempts {} {}", id, attempts)
+            }
+            Update::Progress(id, Progress::Solved(milliseconds)) => {
+                format!("solved {} {}", id, milliseconds)
+            }
+            Update::Progress(id, Progress::Locked) => format!("locked {}", id),
+            Update::Progress(id, Progress::Left) => format!("left {}", id),
+        }
+    }
+
+    pub fn parse(line: &str) -> Option<Update> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        // Every update starts with a number: a player's, or the seed.
+        let number = match words.get(1).and_then(|number| number.parse::<u64>().ok()) {
+            Some(number) => number,
+            None => return None,
+        };
+        let id = number as usize;
+        let value = words.get(2).and_then(|value| value.parse::<i64>().ok());
+        let progress = |progress| Update::Progress(id, progress);
+        match (words[0], words.len()) {
+            ("you", 2) => Some(Update::You(id)),
+            ("player", n) if n > 2 => Some(Update::Player(id, words[2..].join(" "))),
+            ("start", n) if n > 2 => {
+                let words = words[2..].iter().map(|word| word.to_string()).collect();
+                Some(Update::Start(number, words))
+            }
+            ("attempts", 3) => value.map(|n| Progress::Attempts(n as i32)).map(progress),
+            ("solved", 3) => value.map(Progress::Solved).map(progress),
+            ("locked", 2) => Some(progress(Progress::Locked)),
+            ("left", 2) => Some(progress(Progress::Left)),
+            _ => None,
+        }
+    }
+}
+
+pub struct Racer {
+    pub name: String,
+    pub progress: Progress,
+}
+
+/// Everyone in the race and how they're getting on.
+#[derive(Default)]
+pub struct Scoreboard {
+    pub racers: Vec<Racer>,
+
+    /// Which racer is this player, if they're racing.
+    pub me: Option<usize>,
+}
+
+impl Scoreboard {
+    pub fn apply(&mut self, update: &Update) {
+        match *update {
+            Update::You(id) => self.me = Some(id),
+            Update::Player(id, ref name) => {
+                while self.racers.len() <= id {
+                    self.racers.push(Racer {
+                        name: String::new(),
+                        progress: Progress::Attempts(0),
+                    });
+                }
+                self.racers[id].name = name.clone();
+            }
+            Update::Progress(id, progress) => {
+                if let Some(racer) = self.racers.get_mut(id) {
+                    racer.progress = progress;
+                }
+            }
+            Update::Start(..) => (),
+        }
+    }
+
+    /// Whether every racer has solved their terminal, been locked out, or left.
+    pub fn finished(&self) -> bool {
+        self.racers.iter().all(|racer| racer.progress.finished())
+    }
+
+    /// The scoreboard panel: a title over the standings.
+    pub fn panel(&self) -> Vec<String> {
+        let mut lines = vec![i18n::message("race-title")];
+        lines.extend(self.lines());
+        lines
+    }
+
+    /// The standings, best first.
+    pub fn lines(&self) -> Vec<String> {
+        let mut order = (0..self.racers.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&id| self.racers[id].progress.rank());
+
+        let mut lines = vec![];
+        for (place, id) in order.into_iter().enumerate() {
+            let racer = &self.racers[id];
+            let progress = match racer.progress {
+                Progress::Attempts(attempts) => i18n::message_with("race-attempts", &[&attempts]),
+                Progress::Solved(milliseconds) => {
+                    let seconds = milliseconds / 1000;
+                    let time = format!("{}:{:02}", seconds / 60, seconds % 60);
+                    i18n::message_with("race-solved", &[&time])
+                }
+                Progress::Locked => i18n::message("race-locked"),
+                Progress::Left => i18n::message("race-left"),
+            };
+            let marker = if self.me == Some(id) { '>' } else { ' ' };
+            let name = racer.name.chars().take(8).collect::<String>().to_uppercase();
+            lines.push(format!("{}{} {:8} {}", marker, place + 1, name, progress));
+        }
+        lines
+    }
+}
+
+/// Tidies a player's name so that it fits on the scoreboard and in a single message.
+fn clean_name(name: &str, id: usize) -> String {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name = name.chars().take(MAX_NAME_LENGTH).collect::<String>();
+    if name.is_empty() {
+        format!("Player {}", id + 1)
+    } else {
+        name
+    }
+}
+
+/// Reads the line a player joins with, which must name them, and tells them their number and
+/// who has already joined. Returns their name and a reader for the rest of what they send, or
+/// `None` if they didn't join.
+fn greet(stream: &TcpStream,
+         id: usize,
+         scoreboard: &Scoreboard)
+         -> io::Result<Option<(String, BufReader<TcpStream>)>> {
+    stream.set_read_timeout(Some(StdDuration::from_secs(JOIN_TIMEOUT_SECS)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    stream.set_read_timeout(None)?;
+
+    let name = match Request::parse(&line) {
+        Some(Request::Join(name)) => clean_name(&name, id),
+        _ => return Ok(None),
+    };
+
+    let mut stream = stream.try_clone()?;
+    send(&mut stream, &Update::You(id).format())?;
+    for (other, racer) in scoreboard.racers.iter().enumerate() {
+        send(&mut stream, &Update::Player(other, racer.name.clone()).format())?;
+    }
+    Ok(Some((name, reader)))
+}
+
+fn send(stream: &mut TcpStream, line: &str) -> io::Result<()> {
+    stream.write_all(format!("{}\n", line).as_bytes())?;
+    stream.flush()
+}
+
+/// Reads messages from a connection on its own thread, passing them on until the connection
+/// closes, and then passing on `closed`.
+fn read_lines<T, F>(reader: BufReader<TcpStream>, sender: Sender<T>, parse: F, closed: T)
+    where T: Send + 'static,
+          F: Fn(&str) -> Option<T> + Send + 'static
+{
+    thread::spawn(move || {
+        for line in reader.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            match parse(&line) {
+                Some(message) => {
+                    if sender.send(message).is_err() {
+                        return;
+                    }
+                }
+                None => warn!("Ignoring unexpected race message: {}", line),
+            }
+        }
+        let _ = sender.send(closed);
+    });
+}
+
+/// Hosts a race: waits for the given number of players to join, starts them all on the same
+/// board, and passes on everyone's progress until every player has finished. The server doesn't
+/// play itself; it prints the standings as they change.
+pub fn serve(port: u16, players: usize, words: Vec<String>) -> io::Result<()> {
+    run(TcpListener::bind(("0.0.0.0", port))?, players, words)
+}
+
+fn run(listener: TcpListener, players: usize, words: Vec<String>) -> io::Result<()> {
+    println!("Waiting for {} players on port {}.", players, listener.local_addr()?.port());
+
+    let mut scoreboard = Scoreboard::default();
+    let mut streams: Vec<Option<TcpStream>> = vec![];
+    let (sender, receiver) = mpsc::channel();
+
+    while streams.len() < players {
+        let (stream, address) = listener.accept()?;
+        let id = streams.len();
+
+        let (name, reader) = match greet(&stream, id, &scoreboard) {
+            Ok(Some(joined)) => joined,
+            Ok(None) => {
+                println!("{} didn't join properly, ignoring it.", address);
+                continue;
+            }
+            Err(e) => {
+                println!("{} couldn't join: {}", address, e);
+                continue;
+            }
+        };
+
+        read_lines(reader,
+                   sender.clone(),
+                   move |line| Request::parse(line).map(|request| (id, Some(request))),
+                   (id, None));
+        streams.push(Some(stream));
+
+        let update = Update::Player(id, name.clone());
+        scoreboard.apply(&update);
+        broadcast(&mut streams, &update);
+        println!("{} joined from {} ({}/{}).", name, address, id + 1, players);
+    }
+
+    let seed = rand::thread_rng().gen();
+    broadcast(&mut streams, &Update::Start(seed, words));
+    let start = PreciseTime::now();
+    println!("The race is on.");
+
+    while !scoreboard.finished() {
+        let (id, request) = match receiver.recv() {
+            Ok(message) => message,
+            Err(_) => break,
+        };
+        let progress = match request {
+            Some(Request::Attempts(attempts)) => Progress::Attempts(attempts),
+            Some(Request::Solved) => {
+                Progress::Solved(start.to(PreciseTime::now()).num_milliseconds())
+            }
+            Some(Request::Locked) => Progress::Locked,
+            Some(Request::Join(..)) => continue,
+            None => {
+                streams[id] = None;
+                Progress::Left
+            }
+        };
+
+        // Players who have finished can only leave, which doesn't change their result.
+        if scoreboard.racers[id].progress.finished() {
+            continue;
+        }
+
+        let update = Update::Progress(id, progress);
+        scoreboard.apply(&update);
+        broadcast(&mut streams, &update);
+        for line in scoreboard.panel() {
+            println!("{}", line);
+        }
+    }
+
+    println!("Everyone has finished.");
+    Ok(())
+}
+
+fn broadcast(streams: &mut [Option<TcpStream>], update: &Update) {
+    let line = update.format();
+    for stream in streams.iter_mut() {
+        let failed = match *stream {
+            Some(ref mut stream) => send(stream, &line).is_err(),
+            None => false,
+        };
+        if failed {
+            *stream = None;
+        }
+    }
+}
+
+/// A player's connection to a race.
+pub struct RaceClient {
+    stream: TcpStream,
+
+    /// Updates from the server, read on another thread so the game never waits for the network.
+    /// `None` means the server has gone.
+    updates: Receiver<Option<Update>>,
+
+    pub scoreboard: Scoreboard,
+    guesses: i32,
+    connected: bool,
+}
+
+impl RaceClient {
+    /// Joins the race served at an address, given as `HOST` or `HOST:PORT`.
+    pub fn join(address: &str, name: &str) -> io::Result<RaceClient> {
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:{}", address, DEFAULT_PORT)
+        };
+        let mut stream = TcpStream::connect(&address[..])?;
+        send(&mut stream, &Request::Join(name.to_string()).format())?;
+
+        let (sender, receiver) = mpsc::channel();
+        read_lines(BufReader::new(stream.try_clone()?),
+                   sender,
+                   |line| Update::parse(line).map(Some),
+                   None);
+
+        Ok(RaceClient {
+            stream: stream,
+            updates: receiver,
+            scoreboard: Scoreboard::default(),
+            guesses: 0,
+            connected: true,
+        })
+    }
+
+    /// Waits for everyone to join, returning the settings for the race.
+    pub fn wait_for_start(&mut self) -> Result<GameSettings, String> {
+        loop {
+            match self.updates.recv() {
+                Ok(Some(Update::Start(seed, words))) => {
+                    let length = words.first().map_or(0, |word| words::length(word));
+                    let valid = words.len() >= 2 &&
+                                words.iter().all(|word| {
+                        words::normalize(word).as_ref() == Some(word) &&
+                        words::length(word) == length
+                    });
+                    if !valid || length < MIN_DIFFICULTY as usize ||
+                       length > MAX_DIFFICULTY as usize {
+                        return Err("the server sent an unplayable board".to_string());
+                    }
+                    return Ok(GameSettings {
+                        seed: seed,
+                        word_pool: Some(words),
+                        ..GameSettings::new(length as i32)
+                    });
+                }
+                Ok(Some(update)) => self.scoreboard.apply(&update),
+                _ => return Err("the server closed the connection".to_string()),
+            }
+        }
+    }
+
+    /// Applies the updates that have arrived since the last call, returning whether the
+    /// scoreboard changed.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok(update) = self.updates.try_recv() {
+            match update {
+                Some(update) => {
+                    self.scoreboard.apply(&update);
+                    changed = true;
+                }
+                None => {
+                    warn!("The race server closed the connection.");
+                    self.connected = false;
+                }
+            }
+        }
+        changed
+    }
+
+    fn send(&mut self, request: Request) {
+        if !self.connected {
+            return;
+        }
+        if let Err(e) = send(&mut self.stream, &request.format()) {
+            warn!("Could not reach the race server: {}", e);
+            self.connected = false;
+        }
+    }
+}
+
+impl Subscriber for RaceClient {
+    fn notify(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::WordGuessed { .. } => {
+                self.guesses += 1;
+                let guesses = self.guesses;
+                self.send(Request::Attempts(guesses));
+            }
+            GameEvent::Won { bypassed: false } => self.send(Request::Solved),
+
+            // Getting around the password isn't cracking it, so the racer is out.
+            GameEvent::Won { bypassed: true } |
+            GameEvent::LockedOut => self.send(Request::Locked),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::Duration as StdDuration;
+
+    use ::game::{GameEvent, Subscriber};
+    use super::{run, Progress, RaceClient, Request, Update};
+
+    #[test]
+    fn requests_survive_a_round_trip() {
+        let requests = vec![Request::Join("Dr. Li".to_string()),
+                            Request::Attempts(3),
+                            Request::Solved,
+                            Request::Locked];
+        for request in requests {
+            assert_eq!(Request::parse(&request.format()), Some(request));
+        }
+    }
+
+    #[test]
+    fn updates_survive_a_round_trip() {
+        let words = vec!["alpha".to_string(), "bravo".to_string()];
+        let updates = vec![Update::You(2),
+                           Update::Player(1, "Three Dog".to_string()),
+                           Update::Start(18446744073709551615, words),
+                           Update::Progress(0, Progress::Attempts(4)),
+                           Update::Progress(1, Progress::Solved(61500)),
+                           Update::Progress(2, Progress::Locked),
+                           Update::Progress(3, Progress::Left)];
+        for update in updates {
+            assert_eq!(Update::parse(&update.format()), Some(update));
+        }
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        assert_eq!(Request::parse("join"), None);
+        assert_eq!(Request::parse("attempts many"), None);
+        assert_eq!(Request::parse("solved 3"), None);
+        assert_eq!(Update::parse("you"), None);
+        assert_eq!(Update::parse("player 1"), None);
+        assert_eq!(Update::parse("solved 1 soon"), None);
+        assert_eq!(Update::parse("hello 1"), None);
+    }
+
+    /// Polls a client until its scoreboard shows a racer's progress passing a test.
+    fn wait_for<F: Fn(Progress) -> bool>(client: &mut RaceClient, id: usize, test: F) {
+        for _ in 0..500 {
+            client.poll();
+            if test(client.scoreboard.racers[id].progress) {
+                return;
+            }
+            thread::sleep(StdDuration::from_millis(10));
+        }
+        panic!("racer {} is still at {:?}", id, client.scoreboard.racers[id].progress);
+    }
+
+    #[test]
+    fn race_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let words = vec!["alpha".to_string(), "bravo".to_string(), "delta".to_string()];
+        let board = words.clone();
+        let server = thread::spawn(move || run(listener, 2, board));
+
+        // Connections that close without joining are skipped.
+        drop(TcpStream::connect(&address[..]).unwrap());
+
+        let mut alice = RaceClient::join(&address, "alice").unwrap();
+        let mut bob = RaceClient::join(&address, "  bob  ").unwrap();
+        let alice_settings = alice.wait_for_start().unwrap();
+        let bob_settings = bob.wait_for_start().unwrap();
+
+        assert_eq!(alice.scoreboard.me, Some(0));
+        assert_eq!(bob.scoreboard.me, Some(1));
+        for client in &[&alice, &bob] {
+            let names = client.scoreboard.racers.iter().map(|racer| &racer.name[..]);
+            assert_eq!(names.collect::<Vec<_>>(), vec!["alice", "bob"]);
+        }
+        assert_eq!(alice_settings.seed, bob_settings.seed);
+        assert_eq!(alice_settings.word_pool, Some(words.clone()));
+        assert_eq!(bob_settings.word_pool, Some(words));
+        assert_eq!(alice_settings.difficulty, 5);
+
+        alice.notify(&GameEvent::WordGuessed {
+            word: "bravo".to_string(),
+            likeness: 1,
+            correct: false,
+        });
+        wait_for(&mut bob, 0, |progress| progress == Progress::Attempts(1));
+
+        alice.notify(&GameEvent::Won { bypassed: false });
+        bob.notify(&GameEvent::LockedOut);
+        for client in &mut [&mut alice, &mut bob] {
+            wait_for(client, 0, |progress| match progress {
+                Progress::Solved(..) => true,
+                _ => false,
+            });
+            wait_for(client, 1, |progress| progress == Progress::Locked);
+        }
+
+        server.join().unwrap().unwrap();
+    }
+}
@@ -0,0 +1,381 @@
+use std::char;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::io::prelude::*;
+use std::path::Path;
+
+use ::time::Duration;
+
+use ::consts::{BRACKETS_PER_COLUMN, CHARACTERS_PER_COLUMN, COLUMNS, MAX_DIFFICULTY,
+               MIN_DIFFICULTY, NUM_WORDS, STARTING_ATTEMPTS};
+use ::game::{GameSettings, InputEvent};
+use ::words;
+
+/// The first line of every replay file.
+const HEADER: &'static str = "robco-replay 1";
+
+/// Controls available while watching a replay.
+pub enum ReplayControl {
+    TogglePause,
+    Step,
+    Faster,
+    Slower,
+    Screenshot,
+    Quit,
+}
+
+/// A recorded game: the settings it was started with and every input, stamped with the number of
+/// milliseconds since the start of the game.
+pub struct Replay {
+    pub settings: GameSettings,
+    pub events: Vec<(i64, InputEvent)>,
+}
+
+/// Writes a replay file as a game is played.
+///
+/// A replay file is plain text. After the header come `seed` and `difficulty` lines, and
+/// `attempts`, `words`, `brackets`, `pool` and `time-limit` lines if the game didn't use the
+/// defaults. Then there's one line per input, such as `1520 up` or `2310 select 14 9`.
+pub struct Recorder {
+    writer: BufWriter<File>,
+    clock: Duration,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, settings: &GameSettings) -> io::Result<Recorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(writer, "seed {}", settings.seed)?;
+        writeln!(writer, "difficulty {}", settings.difficulty)?;
+        if settings.attempts != STARTING_ATTEMPTS {
+            writeln!(writer, "attempts {}", settings.attempts)?;
+        }
+        if settings.num_words != NUM_WORDS {
+            writeln!(writer, "words {}", settings.num_words)?;
+        }
+        if settings.brackets != BRACKETS_PER_COLUMN {
+            writeln!(writer, "brackets {}", settings.brackets)?;
+        }
+        if let Some(ref pool) = settings.word_pool {
+            writeln!(writer, "pool {}", pool.join(" "))?;
+        }
+        if let Some(ref dictionary) = settings.dictionary {
+            writeln!(writer, "dictionary {}", dictionary)?;
+        }
+        if let Some(time_limit) = settings.time_limit {
+            writeln!(writer, "time-limit {}", time_limit.num_milliseconds())?;
+        }
+
+        Ok(Recorder {
+            writer: writer,
+            clock: Duration::zero(),
+        })
+    }
+
+    /// Records an input, `elapsed` after the previous call.
+    pub fn record(&mut self, event: Option<&InputEvent>, elapsed: Duration) -> io::Result<()> {
+        self.clock = self.clock + elapsed;
+        if let Some(event) = event {
+            writeln!(self.writer,
+                     "{} {}",
+                     self.clock.num_milliseconds(),
+                     format_event(event))?;
+            // Flush every input so the replay survives the game crashing.
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Replay {
+    /// Reads a replay file. Settings that couldn't have been played, such as more words than fit
+    /// on the board, are refused, since the file may have come from anyone.
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut lines = BufReader::new(file).lines().enumerate();
+
+        let error = |line_number: usize, message: &str| {
+            format!("{}:{}: {}", path.display(), line_number + 1, message)
+        };
+
+        // Where the settings that are checked against each other were read, for their errors.
+        let mut difficulty_line = 0;
+        let mut words_line = None;
+        let mut pool_line = 0;
+
+        let mut seed = None;
+        let mut difficulty = None;
+        let mut attempts = STARTING_ATTEMPTS;
+        let mut num_words = NUM_WORDS;
+        let mut brackets = BRACKETS_PER_COLUMN;
+        let mut word_pool = None;
+        let mut dictionary = None;
+        let mut time_limit = None;
+        let mut events = vec![];
+
+        match lines.next() {
+            Some((_, Ok(ref line))) if line == HEADER => (),
+            _ => return Err(error(0, "not a replay file")),
+        }
+
+        for (line_number, line) in lines {
+            let line = line.map_err(|e| format!("{}: {}", path.display(), e))?;
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("seed") => {
+                    seed = words.next().and_then(|w| w.parse().ok());
+                    if seed.is_none() {
+                        return Err(error(line_number, "invalid seed"));
+                    }
+                }
+                Some("difficulty") => {
+                    difficulty = match words.next().and_then(|w| w.parse().ok()) {
+                        Some(n) if n >= MIN_DIFFICULTY && n <= MAX_DIFFICULTY => Some(n),
+                        _ => return Err(error(line_number, "invalid difficulty")),
+                    };
+                    difficulty_line = line_number;
+                }
+                Some("attempts") => {
+                    attempts = match words.next().and_then(|w| w.parse().ok()) {
+                        Some(n) if n > 0 => n,
+                        _ => return Err(error(line_number, "invalid attempts")),
+                    };
+                }
+                Some("words") => {
+                    // Each column needs at least one word, and both get the same number.
+                    num_words = match words.next().and_then(|w| w.parse().ok()) {
+                        Some(n) if n >= COLUMNS && n % COLUMNS == 0 => n,
+                        _ => return Err(error(line_number, "invalid number of words")),
+                    };
+                    words_line = Some(line_number);
+                }
+                Some("brackets") => {
+                    brackets = match words.next().and_then(|w| w.parse().ok()) {
+                        Some(n) if n >= 0 => n,
+                        _ => return Err(error(line_number, "invalid number of brackets")),
+                    };
+                }
+                Some("pool") => {
+                    word_pool = Some(words.map(String::from).collect::<Vec<_>>());
+                    pool_line = line_number;
+                }
+                // The path is the rest of the line, since it may contain spaces.
+                Some("dictionary") => {
+                    dictionary = Some(line["dictionary".len()..].trim().to_string());
+                }
+                Some("time-limit") => {
+                    let milliseconds = words.next()
+                        .and_then(|w| w.parse().ok())
+                        .ok_or_else(|| error(line_number, "invalid time limit"))?;
+                    time_limit = Some(Duration::milliseconds(milliseconds));
+                }
+                Some(time) => {
+                    let time = time.parse()
+                        .map_err(|_| error(line_number, "expected a timestamp"))?;
+                    let event = parse_event(&words.collect::<Vec<_>>())
+                        .ok_or_else(|| error(line_number, "invalid input"))?;
+                    events.push((time, event));
+                }
+                None => (),
+            }
+        }
+
+        let (seed, difficulty) = match (seed, difficulty) {
+            (Some(seed), Some(difficulty)) => (seed, difficulty),
+            _ => return Err(format!("{}: missing seed or difficulty", path.display())),
+        };
+
+        // Every word needs a space in its column longer than itself.
+        let words_per_column = num_words / COLUMNS;
+        if CHARACTERS_PER_COLUMN / words_per_column <= difficulty {
+            let line_number = words_line.unwrap_or(difficulty_line);
+            return Err(error(line_number, "too many words to fit on the board"));
+        }
+
+        if let Some(ref pool) = word_pool {
+            if pool.len() < num_words as usize {
+                return Err(error(pool_line, "fewer words in the pool than on the board"));
+            }
+            if pool.iter().any(|word| words::length(word) as i32 != difficulty) {
+                return Err(error(pool_line, "words in the pool must match the difficulty"));
+            }
+        }
+
+        Ok(Replay {
+            settings: GameSettings {
+                difficulty: difficulty,
+                seed: seed,
+                attempts: attempts,
+                num_words: num_words,
+                brackets: brackets,
+                word_pool: word_pool,
+                dictionary: dictionary,
+                time_limit: time_limit,
+            },
+            events: events,
+        })
+    }
+}
+
+fn format_event(event: &InputEvent) -> String {
+    match *event {
+        InputEvent::Up => "up".to_string(),
+        InputEvent::Down => "down".to_string(),
+        InputEvent::Left => "left".to_string(),
+        InputEvent::Right => "right".to_string(),
+        InputEvent::Action => "action".to_string(),
+        InputEvent::Quit => "quit".to_string(),
+        InputEvent::ToggleMute => "mute".to_string(),
+        InputEvent::Screenshot => "screenshot".to_string(),
+        InputEvent::MoveTo(x, y) => format!("move {} {}", x, y),
+        InputEvent::SelectAt(x, y) => format!("select {} {}", x, y),
+        InputEvent::InspectAt(x, y) => format!("inspect {} {}", x, y),
+        InputEvent::ScrollConsole(rows) => format!("scroll {}", rows),
+        InputEvent::ShowLog => "log".to_string(),
+        InputEvent::CommandPrompt => "prompt".to_string(),
+        InputEvent::Erase => "erase".to_string(),
+
+        // Characters are written as numbers, since a space would split the line.
+        InputEvent::Type(c) => format!("type {}", c as u32),
+    }
+}
+
+fn parse_event(words: &[&str]) -> Option<InputEvent> {
+    match words.len() {
+        1 => {
+            match words[0] {
+                "up" => Some(InputEvent::Up),
+                "down" => Some(InputEvent::Down),
+                "left" => Some(InputEvent::Left),
+                "right" => Some(InputEvent::Right),
+                "action" => Some(InputEvent::Action),
+                "quit" => Some(InputEvent::Quit),
+                "mute" => Some(InputEvent::ToggleMute),
+                "screenshot" => Some(InputEvent::Screenshot),
+                "prompt" => Some(InputEvent::CommandPrompt),
+                "erase" => Some(InputEvent::Erase),
+                "log" => Some(InputEvent::ShowLog),
+                _ => None,
+            }
+        }
+        2 => {
+            match words[0] {
+                "type" => words[1].parse().ok().and_then(char::from_u32).map(InputEvent::Type),
+                "scroll" => words[1].parse().ok().map(InputEvent::ScrollConsole),
+                _ => None,
+            }
+        }
+        3 => {
+            let (x, y) = match (words[1].parse(), words[2].parse()) {
+                (Ok(x), Ok(y)) => (x, y),
+                _ => return None,
+            };
+            match words[0] {
+                "move" => Some(InputEvent::MoveTo(x, y)),
+                "select" => Some(InputEvent::SelectAt(x, y)),
+                "inspect" => Some(InputEvent::InspectAt(x, y)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    use ::game::{GameSettings, InputEvent};
+    use ::time::Duration;
+
+    use super::{Recorder, Replay, HEADER};
+
+    /// Loads a replay file with the given settings lines. Errors name the file without its
+    /// directory.
+    fn load(name: &str, lines: &[&str]) -> Result<Replay, String> {
+        let dir = env::temp_dir().join(format!("robco-term-replay-{}", ::std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "{}", HEADER).unwrap();
+        for line in lines {
+            writeln!(file, "{}", line).unwrap();
+        }
+        drop(file);
+
+        let replay = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+        replay.map_err(|e| e.replace(&path.display().to_string(), name))
+    }
+
+    fn error(result: Result<Replay, String>) -> String {
+        result.err().expect("the replay should be refused")
+    }
+
+    #[test]
+    fn recorded_games_load() {
+        let dir = env::temp_dir().join(format!("robco-term-replay-{}", ::std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("recorded");
+        let settings = GameSettings {
+            num_words: 6,
+            word_pool: Some(["alpha", "bravo", "delta", "gamma", "omega", "sigma"]
+                .iter()
+                .map(|word| word.to_string())
+                .collect()),
+            ..GameSettings::new(5)
+        };
+        {
+            let mut recorder = Recorder::create(&path, &settings).unwrap();
+            recorder.record(Some(&InputEvent::SelectAt(3, 4)), Duration::milliseconds(250))
+                .unwrap();
+        }
+
+        let replay = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(replay.settings.num_words, 6);
+        assert_eq!(replay.settings.word_pool, settings.word_pool);
+        assert_eq!(replay.events.len(), 1);
+    }
+
+    #[test]
+    fn settings_that_cant_be_played_are_refused() {
+        let invalid = [("difficulty 3", "invalid difficulty"),
+                       ("difficulty 13", "invalid difficulty"),
+                       ("attempts 0", "invalid attempts"),
+                       ("attempts -1", "invalid attempts"),
+                       ("words 0", "invalid number of words"),
+                       ("words 1", "invalid number of words"),
+                       ("words 7", "invalid number of words"),
+                       ("words 100", "too many words to fit on the board"),
+                       ("brackets -1", "invalid number of brackets")];
+        for &(line, message) in &invalid {
+            let result = load("invalid", &["seed 1", "difficulty 5", line]);
+            assert_eq!(error(result), format!("invalid:4: {}", message));
+        }
+    }
+
+    #[test]
+    fn the_pool_must_fill_the_board() {
+        let short = load("pool", &["seed 1", "difficulty 5", "words 4", "pool alpha bravo delta"]);
+        assert_eq!(error(short), "pool:5: fewer words in the pool than on the board");
+
+        let wrong_length = load("pool", &["seed 1", "difficulty 5", "words 2", "pool alpha be"]);
+        assert_eq!(error(wrong_length), "pool:5: words in the pool must match the difficulty");
+
+        let enough = load("pool", &["seed 1", "difficulty 5", "words 2", "pool alpha bravo"]);
+        assert!(enough.is_ok());
+    }
+
+    #[test]
+    fn longer_words_leave_room_for_fewer() {
+        // A column of 17 rows of 12 characters has room for 15 words of 12 letters, but not 16.
+        assert!(load("fit", &["seed 1", "difficulty 12", "words 30"]).is_ok());
+        assert_eq!(error(load("fit", &["seed 1", "difficulty 12", "words 32"])),
+                   "fit:4: too many words to fit on the board");
+        assert_eq!(error(load("fit", &["seed 1", "words 32", "difficulty 12"])),
+                   "fit:3: too many words to fit on the board");
+    }
+}
@@ -0,0 +1,108 @@
+use std::slice::Chunks;
+
+/// How a single character on the screen should be drawn.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub highlight: bool,
+    pub dim: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Cell {
+        Cell {
+            ch: ' ',
+            style: Style::default(),
+        }
+    }
+}
+
+/// An in-memory grid of characters that a frame is drawn into before it is displayed. Keeping
+/// the frame around lets us post-process it (for effects) independently of the backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screen {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+
+    /// The position of the visible cursor, if there is one, as `(x, y)`.
+    pub cursor: Option<(i32, i32)>,
+}
+
+impl Screen {
+    pub fn new(width: i32, height: i32) -> Screen {
+        let width = if width > 0 { width as usize } else { 0 };
+        let height = if height > 0 { height as usize } else { 0 };
+        Screen {
+            width: width,
+            height: height,
+            cells: vec![Cell::default(); width * height],
+            cursor: None,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width as i32
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height as i32
+    }
+
+    /// Prints a string starting at the given position. Anything that falls off the edge of the
+    /// screen is discarded.
+    pub fn print(&mut self, row: i32, col: i32, text: &str) {
+        for (i, ch) in text.chars().enumerate() {
+            self.put(row, col + i as i32, ch);
+        }
+    }
+
+    pub fn put(&mut self, row: i32, col: i32, ch: char) {
+        if let Some(cell) = self.cell_mut(row, col) {
+            *cell = Cell { ch: ch, ..Cell::default() };
+        }
+    }
+
+    pub fn get(&self, row: i32, col: i32) -> Option<Cell> {
+        self.index(row, col).map(|index| self.cells[index])
+    }
+
+    pub fn cell_mut(&mut self, row: i32, col: i32) -> Option<&mut Cell> {
+        match self.index(row, col) {
+            Some(index) => Some(&mut self.cells[index]),
+            None => None,
+        }
+    }
+
+    pub fn row_mut(&mut self, row: i32) -> &mut [Cell] {
+        let start = row as usize * self.width;
+        &mut self.cells[start..start + self.width]
+    }
+
+    pub fn rows(&self) -> Chunks<Cell> {
+        self.cells.chunks(if self.width > 0 { self.width } else { 1 })
+    }
+
+    /// Moves every row up by `offset` rows, wrapping the top rows around to the bottom.
+    pub fn roll(&mut self, offset: i32) {
+        if self.height == 0 {
+            return;
+        }
+        let offset = (offset as usize % self.height) * self.width;
+        let mut rolled = self.cells.split_off(offset);
+        rolled.extend(self.cells.drain(..));
+        self.cells = rolled;
+    }
+
+    fn index(&self, row: i32, col: i32) -> Option<usize> {
+        if row < 0 || col < 0 || row as usize >= self.height || col as usize >= self.width {
+            return None;
+        }
+        Some(row as usize * self.width + col as usize)
+    }
+}
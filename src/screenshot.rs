@@ -0,0 +1,182 @@
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use ::itertools::Itertools;
+use ::time;
+
+use ::screen::{Cell, Screen, Style};
+use ::theme::{self, Theme};
+
+/// The width and height of a character in SVG screenshots, in pixels.
+const SVG_CELL_WIDTH: f64 = 8.4;
+const SVG_CELL_HEIGHT: f64 = 17.0;
+
+/// How opaque dimmed text is in HTML and SVG screenshots.
+const DIM_OPACITY: f64 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Html,
+    Svg,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match &name.to_lowercase()[..] {
+            "txt" | "text" => Some(Format::Text),
+            "html" | "htm" => Some(Format::Html),
+            "svg" => Some(Format::Svg),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from a file's extension.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        path.extension().and_then(|extension| extension.to_str()).and_then(Format::parse)
+    }
+
+    fn extension(&self) -> &'static str {
+        match *self {
+            Format::Text => "txt",
+            Format::Html => "html",
+            Format::Svg => "svg",
+        }
+    }
+}
+
+/// Picks a name for a new screenshot in the current directory.
+pub fn default_path(format: Format) -> PathBuf {
+    let now = time::now();
+    let stamp = time::strftime("%Y%m%d-%H%M%S", &now).unwrap();
+    PathBuf::from(format!("robco-{}.{}", stamp, format.extension()))
+}
+
+/// Writes a screen to a file.
+pub fn save(path: &Path, screen: &Screen, theme: &Theme, format: Format) -> io::Result<()> {
+    let contents = match format {
+        Format::Text => render_text(screen),
+        Format::Html => render_html(screen, theme),
+        Format::Svg => render_svg(screen, theme),
+    };
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(contents.as_bytes())?;
+    writer.flush()
+}
+
+fn render_text(screen: &Screen) -> String {
+    let mut text = String::new();
+    for row in screen.rows() {
+        let line = row.iter().map(|cell| cell.ch).collect::<String>();
+        text.push_str(line.trim_right());
+        text.push('\n');
+    }
+    text
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn run_text(run: &[&Cell]) -> String {
+    escape(&run.iter().map(|cell| cell.ch).collect::<String>())
+}
+
+fn render_html(screen: &Screen, theme: &Theme) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>RobCo Industries Termlink</title>\n<style>\n");
+    html.push_str(&format!("pre {{ display: inline-block; padding: 1em; color: {}; \
+                            background: {}; font-family: monospace; }}\n",
+                           theme::color_rgb(theme.normal.foreground),
+                           theme::color_rgb(theme.normal.background)));
+    html.push_str(&format!(".highlight {{ color: {}; background: {}; }}\n",
+                           theme::color_rgb(theme.highlight.foreground),
+                           theme::color_rgb(theme.highlight.background)));
+    html.push_str(&format!(".dim {{ opacity: {}; }}\n", DIM_OPACITY));
+    html.push_str("</style>\n</head>\n<body>\n<pre>");
+
+    for row in screen.rows() {
+        for (style, run) in &row.iter().group_by(|cell| cell.style) {
+            let text = run_text(&run.collect::<Vec<_>>());
+            let classes = style_classes(style);
+            if classes.is_empty() {
+                html.push_str(&text);
+            } else {
+                html.push_str(&format!("<span class=\"{}\">{}</span>", classes.join(" "), text));
+            }
+        }
+        html.push('\n');
+    }
+
+    html.push_str("</pre>\n</body>\n</html>\n");
+    html
+}
+
+fn style_classes(style: Style) -> Vec<&'static str> {
+    let mut classes = vec![];
+    if style.highlight {
+        classes.push("highlight");
+    }
+    if style.dim {
+        classes.push("dim");
+    }
+    classes
+}
+
+fn render_svg(screen: &Screen, theme: &Theme) -> String {
+    let width = screen.width() as f64 * SVG_CELL_WIDTH;
+    let height = screen.height() as f64 * SVG_CELL_HEIGHT;
+
+    let mut svg = String::new();
+    svg.push_str(&format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+                           font-family=\"monospace\" font-size=\"14\">\n",
+                          width,
+                          height));
+    svg.push_str(&format!("<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+                          theme::color_rgb(theme.normal.background)));
+
+    for (row, cells) in screen.rows().enumerate() {
+        let y = row as f64 * SVG_CELL_HEIGHT;
+        let mut col = 0;
+        for (style, run) in &cells.iter().group_by(|cell| cell.style) {
+            let run = run.collect::<Vec<_>>();
+            let x = col as f64 * SVG_CELL_WIDTH;
+            let run_width = run.len() as f64 * SVG_CELL_WIDTH;
+            col += run.len();
+
+            let colors = if style.highlight {
+                svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" \
+                                       fill=\"{}\"/>\n",
+                                      x,
+                                      y,
+                                      run_width,
+                                      SVG_CELL_HEIGHT,
+                                      theme::color_rgb(theme.highlight.background)));
+                theme.highlight
+            } else {
+                theme.normal
+            };
+
+            if run.iter().all(|cell| cell.ch == ' ') {
+                continue;
+            }
+
+            let opacity = if style.dim { DIM_OPACITY } else { 1.0 };
+            svg.push_str(&format!("<text x=\"{}\" y=\"{}\" textLength=\"{}\" fill=\"{}\" \
+                                   fill-opacity=\"{}\" xml:space=\"preserve\">{}</text>\n",
+                                  x,
+                                  y + SVG_CELL_HEIGHT * 0.8,
+                                  run_width,
+                                  theme::color_rgb(colors.foreground),
+                                  opacity,
+                                  run_text(&run)));
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
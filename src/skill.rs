@@ -0,0 +1,146 @@
+use std::cmp;
+
+use ::game::GameSettings;
+use ::i18n;
+
+/// However much skill the player has, a board keeps this many words.
+const MIN_SKILLED_WORDS: i32 = 4;
+
+/// How hard a terminal is to hack. In the games, each level needs a higher Science skill to even
+/// attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockLevel {
+    VeryEasy,
+    Easy,
+    Average,
+    Hard,
+    VeryHard,
+}
+
+pub const LOCK_LEVELS: [LockLevel; 5] = [LockLevel::VeryEasy,
+                                         LockLevel::Easy,
+                                         LockLevel::Average,
+                                         LockLevel::Hard,
+                                         LockLevel::VeryHard];
+
+impl LockLevel {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            LockLevel::VeryEasy => "very-easy",
+            LockLevel::Easy => "easy",
+            LockLevel::Average => "average",
+            LockLevel::Hard => "hard",
+            LockLevel::VeryHard => "very-hard",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<LockLevel> {
+        LOCK_LEVELS.iter().cloned().find(|level| level.name() == name)
+    }
+
+    /// The Science skill needed to attempt a terminal of this level.
+    pub fn required_skill(&self) -> i32 {
+        match *self {
+            LockLevel::VeryEasy => 0,
+            LockLevel::Easy => 25,
+            LockLevel::Average => 50,
+            LockLevel::Hard => 75,
+            LockLevel::VeryHard => 100,
+        }
+    }
+}
+
+/// The player's Science skill and the lock level of the terminals they're hacking.
+#[derive(Debug, Clone, Copy)]
+pub struct Skill {
+    pub science: i32,
+    pub lock_level: LockLevel,
+}
+
+impl Skill {
+    pub fn can_hack(&self) -> bool {
+        self.science >= self.lock_level.required_skill()
+    }
+
+    /// The message shown when the player's skill is too low, in the words of the terminal.
+    pub fn refusal(&self) -> Vec<String> {
+        vec![i18n::message("access-denied"),
+             String::new(),
+             i18n::message_with("skill-required", &[&self.lock_level.required_skill()]),
+             i18n::message_with("skill-yours", &[&self.science])]
+    }
+
+    /// Biases a game towards the player. With just the skill the lock level requires, the board
+    /// is the one the settings describe. Every ten points above that adds a bracket pair to each
+    /// column, and every twenty takes a candidate word off each column, down to two a column.
+    pub fn apply(&self, settings: GameSettings) -> GameSettings {
+        let margin = cmp::max(0, self.science - self.lock_level.required_skill()) / 10;
+
+        // Words are split evenly between the columns. A board that already has fewer words than
+        // the fewest the skill leaves keeps them all.
+        let fewest = cmp::min(MIN_SKILLED_WORDS, settings.num_words);
+        let num_words = cmp::max(fewest, settings.num_words - margin / 2 * 2);
+
+        GameSettings {
+            num_words: num_words,
+            brackets: settings.brackets + margin,
+            ..settings
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::game::GameSettings;
+    use super::{LockLevel, Skill};
+
+    #[test]
+    fn skill_at_the_requirement_gives_the_usual_board() {
+        let skill = Skill {
+            science: 50,
+            lock_level: LockLevel::Average,
+        };
+        let usual = GameSettings::new(5);
+        let settings = skill.apply(GameSettings::new(5));
+        assert_eq!(settings.num_words, usual.num_words);
+        assert_eq!(settings.brackets, usual.brackets);
+    }
+
+    #[test]
+    fn more_skill_gives_an_easier_board() {
+        let usual = GameSettings::new(5);
+        let mut last = usual.clone();
+        for science in (6..11).map(|tens| tens * 10) {
+            let skill = Skill {
+                science: science,
+                lock_level: LockLevel::Average,
+            };
+            let settings = skill.apply(GameSettings::new(5));
+            assert!(settings.num_words <= last.num_words);
+            assert!(settings.num_words % 2 == 0 && settings.num_words >= 4);
+            assert!(settings.brackets > last.brackets);
+            last = settings;
+        }
+        assert!(last.num_words < usual.num_words);
+    }
+
+    #[test]
+    fn skill_scales_the_board_it_is_given() {
+        let skill = Skill {
+            science: 100,
+            lock_level: LockLevel::Average,
+        };
+        let custom = GameSettings {
+            num_words: 8,
+            brackets: 2,
+            ..GameSettings::new(5)
+        };
+        let settings = skill.apply(custom.clone());
+        assert_eq!(settings.num_words, 4);
+        assert_eq!(settings.brackets, 7);
+
+        // Small boards aren't made any smaller, or bigger.
+        let small = GameSettings { num_words: 2, ..custom };
+        assert_eq!(skill.apply(small).num_words, 2);
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use ::game::{BracketReward, GameEvent};
+use ::keyfile;
+
+/// The moments in a game that can make a sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cue {
+    GuessDenied,
+    DudRemoved,
+    AllowanceReplenished,
+    AccessGranted,
+    Lockout,
+}
+
+impl Cue {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Cue::GuessDenied => "guess-denied",
+            Cue::DudRemoved => "dud-removed",
+            Cue::AllowanceReplenished => "allowance-replenished",
+            Cue::AccessGranted => "access-granted",
+            Cue::Lockout => "lockout",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Cue> {
+        CUES.iter().cloned().find(|cue| cue.name() == name)
+    }
+}
+
+pub const CUES: [Cue; 5] = [Cue::GuessDenied,
+                            Cue::DudRemoved,
+                            Cue::AllowanceReplenished,
+                            Cue::AccessGranted,
+                            Cue::Lockout];
+
+/// What to play for a cue.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sound {
+    Bell,
+    File(PathBuf),
+    Silent,
+}
+
+impl Sound {
+    /// Parses a sound from a sound map. `bell` and `off` are special, anything else is a path to a
+    /// sound file.
+    pub fn parse(value: &str) -> Sound {
+        match value {
+            "bell" => Sound::Bell,
+            "off" => Sound::Silent,
+            path => Sound::File(PathBuf::from(path)),
+        }
+    }
+}
+
+/// How sounds are actually played.
+#[derive(Debug, Clone)]
+pub enum Backend {
+    /// Rings the terminal bell. Sound files are played as a bell too.
+    Bell,
+
+    /// Runs an external command with the path of the sound file as its last argument, for
+    /// example `aplay -q`. Cues that are mapped to `bell` still ring the terminal bell.
+    Command(String),
+}
+
+#[derive(Clone)]
+pub struct SoundPlayer {
+    backend: Backend,
+    sounds: HashMap<Cue, Sound>,
+    muted: bool,
+
+    /// Whether a cue has rung the bell since the window last rang it. The bell is left to the
+    /// window, which knows where the player's terminal is.
+    bell: bool,
+}
+
+impl SoundPlayer {
+    /// Creates a player that rings the bell for every cue.
+    pub fn new(backend: Backend) -> SoundPlayer {
+        SoundPlayer {
+            backend: backend,
+            sounds: CUES.iter().map(|&cue| (cue, Sound::Bell)).collect(),
+            muted: false,
+            bell: false,
+        }
+    }
+
+    /// Overrides the sounds for some cues with those in a sound map file. Each line of the file
+    /// looks like `guess-denied = /path/to/beep.wav`, where the value may also be `bell` or
+    /// `off`. Blank lines and lines starting with `#` are ignored.
+    pub fn load_sound_map(&mut self, path: &Path) -> Result<(), String> {
+        let sounds = &mut self.sounds;
+        keyfile::read(path, "event = sound", |_, key, value| {
+            let cue = Cue::from_name(key).ok_or_else(|| format!("unknown event '{}'", key))?;
+            sounds.insert(cue, Sound::parse(value));
+            Ok(())
+        })
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        info!("Sound {}.", if self.muted { "muted" } else { "unmuted" });
+    }
+
+    pub fn muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Whether the bell should ring, which it then no longer needs to.
+    pub fn take_bell(&mut self) -> bool {
+        mem::replace(&mut self.bell, false)
+    }
+
+    pub fn play(&mut self, cue: Cue) {
+        if self.muted {
+            return;
+        }
+
+        match (self.sounds.get(&cue), &self.backend) {
+            (Some(&Sound::File(ref path)), &Backend::Command(ref command)) => {
+                run_player(command, path);
+            }
+            (Some(&Sound::File(..)), &Backend::Bell) |
+            (Some(&Sound::Bell), _) => {
+                self.bell = true;
+            }
+            (Some(&Sound::Silent), _) | (None, _) => (),
+        }
+    }
+
+    /// Plays the cues for the events of one update.
+    pub fn notify_all(&mut self, events: &[GameEvent]) {
+        for cue in cues(events) {
+            self.play(cue);
+        }
+    }
+}
+
+fn cue(event: &GameEvent) -> Option<Cue> {
+    match *event {
+        GameEvent::WordGuessed { correct: false, .. } => Some(Cue::GuessDenied),
+        GameEvent::BracketTriggered { reward: BracketReward::DudRemoved } => Some(Cue::DudRemoved),
+        GameEvent::BracketTriggered { reward: BracketReward::AllowanceReplenished } => {
+            Some(Cue::AllowanceReplenished)
+        }
+        GameEvent::Won { .. } => Some(Cue::AccessGranted),
+        GameEvent::LockedOut => Some(Cue::Lockout),
+        GameEvent::WordGuessed { correct: true, .. } |
+        GameEvent::CursorMoved { .. } => None,
+    }
+}
+
+/// The cues for the events of one update. The last wrong guess also locks the terminal, and
+/// only the lockout is heard then.
+fn cues(events: &[GameEvent]) -> Vec<Cue> {
+    let cues = events.iter().filter_map(cue).collect::<Vec<_>>();
+    if cues.contains(&Cue::Lockout) {
+        cues.into_iter().filter(|&cue| cue != Cue::GuessDenied).collect()
+    } else {
+        cues
+    }
+}
+
+/// Starts the external player in the background. A broken player shouldn't take the game down
+/// with it, so failures are only logged.
+fn run_player(command: &str, path: &Path) {
+    let mut words = command.split_whitespace();
+    let program = match words.next() {
+        Some(program) => program,
+        None => return,
+    };
+
+    let result = Command::new(program)
+        .args(&words.collect::<Vec<_>>())
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    match result {
+        Ok(mut child) => {
+            // Reap the player once it's done so it doesn't linger as a zombie.
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => warn!("Could not run sound command '{}': {}", command, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cues, Cue};
+    use ::game::GameEvent;
+
+    fn guess(correct: bool) -> GameEvent {
+        GameEvent::WordGuessed {
+            word: "WORD".to_string(),
+            likeness: 0,
+            correct: correct,
+        }
+    }
+
+    #[test]
+    fn wrong_guesses_are_denied() {
+        assert_eq!(cues(&[guess(false)]), vec![Cue::GuessDenied]);
+        let won = GameEvent::Won { bypassed: false };
+        assert_eq!(cues(&[guess(true), won]), vec![Cue::AccessGranted]);
+    }
+
+    #[test]
+    fn the_last_wrong_guess_only_locks_out() {
+        assert_eq!(cues(&[guess(false), GameEvent::LockedOut]), vec![Cue::Lockout]);
+    }
+}
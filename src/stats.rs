@@ -0,0 +1,394 @@
+use std::cmp;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader};
+use std::io::prelude::*;
+use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
+
+use ::time::{self, Duration, PreciseTime, Timespec};
+
+use ::game::{BracketReward, GameEnding, GameEvent, GameSettings, Subscriber};
+use ::i18n;
+use ::paths;
+
+/// The name of the statistics file in the data directory.
+const STATS_FILE: &'static str = "stats.tsv";
+
+/// How many of the fastest solves to show.
+const FASTEST_SOLVES: usize = 5;
+
+/// The outcome of one finished game.
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    /// When the game finished, in seconds since the epoch.
+    pub timestamp: i64,
+    pub seed: u64,
+    pub difficulty: i32,
+    pub result: GameEnding,
+    pub attempts_used: i32,
+    pub brackets_consumed: i32,
+
+    /// The number of brackets that removed a dud, giving the player a hint.
+    pub hints_used: i32,
+    pub duration: Duration,
+}
+
+impl GameRecord {
+    fn to_line(&self) -> String {
+        format!("{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                self.timestamp,
+                self.seed,
+                self.difficulty,
+                match self.result {
+                    GameEnding::Won => "won",
+                    GameEnding::Lost => "lost",
+                },
+                self.attempts_used,
+                self.brackets_consumed,
+                self.hints_used,
+                self.duration.num_milliseconds())
+    }
+
+    fn from_line(line: &str) -> Option<GameRecord> {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        if fields.len() != 8 {
+            return None;
+        }
+
+        let result = match fields[3] {
+            "won" => GameEnding::Won,
+            "lost" => GameEnding::Lost,
+            _ => return None,
+        };
+
+        GameRecord::parse_numbers(&fields, result).ok()
+    }
+
+    fn parse_numbers(fields: &[&str], result: GameEnding) -> Result<GameRecord, ParseIntError> {
+        Ok(GameRecord {
+            timestamp: fields[0].parse()?,
+            seed: fields[1].parse()?,
+            difficulty: fields[2].parse()?,
+            result: result,
+            attempts_used: fields[4].parse()?,
+            brackets_consumed: fields[5].parse()?,
+            hints_used: fields[6].parse()?,
+            duration: Duration::milliseconds(fields[7].parse()?),
+        })
+    }
+}
+
+/// Measures the time between one lap and the next, so that every moment of a game is counted
+/// once, however the time is spent.
+pub struct Stopwatch {
+    last: PreciseTime,
+}
+
+impl Stopwatch {
+    pub fn start() -> Stopwatch {
+        Stopwatch { last: PreciseTime::now() }
+    }
+
+    /// The time since the last lap, or since the stopwatch was started.
+    pub fn lap(&mut self) -> Duration {
+        let now = PreciseTime::now();
+        let elapsed = self.last.to(now);
+        self.last = now;
+        elapsed
+    }
+}
+
+/// Watches a game as it's played and produces a record of it once it ends.
+pub struct StatsTracker {
+    settings: GameSettings,
+    attempts_used: i32,
+    brackets_consumed: i32,
+    hints_used: i32,
+    duration: Duration,
+    result: Option<GameEnding>,
+}
+
+impl StatsTracker {
+    pub fn new(settings: &GameSettings) -> StatsTracker {
+        StatsTracker {
+            settings: settings.clone(),
+            attempts_used: 0,
+            brackets_consumed: 0,
+            hints_used: 0,
+            duration: Duration::zero(),
+            result: None,
+        }
+    }
+
+    /// Counts time spent in the game, until it ends.
+    pub fn update(&mut self, elapsed: Duration) {
+        if self.result.is_none() {
+            self.duration = self.duration + elapsed;
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// The record of the game, if it has finished.
+    pub fn record(&self) -> Option<GameRecord> {
+        self.result.map(|result| {
+            GameRecord {
+                timestamp: time::get_time().sec,
+                seed: self.settings.seed,
+                difficulty: self.settings.difficulty,
+                result: result,
+                attempts_used: self.attempts_used,
+                brackets_consumed: self.brackets_consumed,
+                hints_used: self.hints_used,
+                duration: self.duration,
+            }
+        })
+    }
+}
+
+impl Subscriber for StatsTracker {
+    fn notify(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::WordGuessed { .. } => self.attempts_used += 1,
+            GameEvent::BracketTriggered { reward } => {
+                self.brackets_consumed += 1;
+                if reward == BracketReward::DudRemoved {
+                    self.hints_used += 1;
+                }
+            }
+            GameEvent::Won { bypassed: false } => self.result = Some(GameEnding::Won),
+            GameEvent::LockedOut => self.result = Some(GameEnding::Lost),
+
+            // A game won without guessing the password isn't kept, so it never gets a score.
+            GameEvent::Won { bypassed: true } |
+            GameEvent::CursorMoved { .. } => (),
+        }
+    }
+}
+
+/// The history of every finished game, kept in a tab-separated file in the data directory.
+pub struct StatsStore {
+    path: PathBuf,
+}
+
+impl StatsStore {
+    pub fn open() -> io::Result<StatsStore> {
+        Ok(StatsStore { path: paths::data_file(STATS_FILE)? })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, record: &GameRecord) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", record.to_line())
+    }
+
+    /// Reads every record, oldest first. Lines that can't be read are skipped.
+    pub fn load(&self) -> io::Result<Vec<GameRecord>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        let mut records = vec![];
+        for (line_number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            match GameRecord::from_line(&line) {
+                Some(record) => records.push(record),
+                None => {
+                    warn!("{}:{}: skipping invalid record",
+                          self.path.display(),
+                          line_number + 1)
+                }
+            }
+        }
+        Ok(records)
+    }
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        100.0 * part as f64 / whole as f64
+    }
+}
+
+fn average(total: i32, count: usize) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        total as f64 / count as f64
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.num_seconds();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+pub fn format_date(timestamp: i64) -> String {
+    let tm = time::at(Timespec::new(timestamp, 0));
+    time::strftime("%Y-%m-%d", &tm).unwrap()
+}
+
+/// Summarizes the history as the text printed by the `stats` command.
+pub fn summary(records: &[GameRecord]) -> String {
+    if records.is_empty() {
+        return format!("{}\n", i18n::message("stats-none"));
+    }
+
+    let won = |record: &&GameRecord| record.result == GameEnding::Won;
+    let wins = records.iter().filter(&won).count();
+
+    let mut longest_streak = 0;
+    let mut streak = 0;
+    for record in records {
+        if won(&record) {
+            streak += 1;
+            longest_streak = cmp::max(longest_streak, streak);
+        } else {
+            streak = 0;
+        }
+    }
+
+    let totals = vec![("stats-played", records.len().to_string()),
+                      ("stats-won", format!("{} ({:.0}%)", wins, percent(wins, records.len()))),
+                      ("stats-streak", streak.to_string()),
+                      ("stats-longest-streak", longest_streak.to_string()),
+                      ("stats-average-guesses",
+                       format!("{:.1}",
+                               average(records.iter().map(|r| r.attempts_used).sum(),
+                                       records.len()))),
+                      ("stats-average-hints",
+                       format!("{:.1}",
+                               average(records.iter().map(|r| r.hints_used).sum(),
+                                       records.len())))];
+    let totals = totals.into_iter()
+        .map(|(key, value)| (i18n::message(key), value))
+        .collect::<Vec<_>>();
+
+    // The labels are translated, so the values line up after whichever is longest.
+    let width = totals.iter().map(|&(ref label, _)| label.chars().count()).max().unwrap_or(0);
+    let mut lines = totals.iter()
+        .map(|&(ref label, ref value)| format!("{:<width$}   {}", label, value, width = width))
+        .collect::<Vec<_>>();
+
+    let mut by_difficulty = BTreeMap::new();
+    for record in records {
+        by_difficulty.entry(record.difficulty).or_insert_with(Vec::new).push(record);
+    }
+
+    let headings = ["stats-difficulty",
+                    "stats-played-column",
+                    "stats-won-column",
+                    "stats-win-rate",
+                    "stats-guesses-to-win"];
+    let headings = headings.iter().map(|key| i18n::message(key)).collect::<Vec<_>>();
+    let rows = by_difficulty.iter()
+        .map(|(difficulty, games)| {
+            let wins = games.iter().cloned().filter(&won).collect::<Vec<_>>();
+            vec![difficulty.to_string(),
+                 games.len().to_string(),
+                 wins.len().to_string(),
+                 format!("{:.0}%", percent(wins.len(), games.len())),
+                 format!("{:.1}",
+                         average(wins.iter().map(|r| r.attempts_used).sum(), wins.len()))]
+        })
+        .collect::<Vec<_>>();
+    lines.push(String::new());
+    lines.extend(table(&headings, &rows));
+
+    let mut fastest = records.iter().filter(&won).collect::<Vec<_>>();
+    fastest.sort_by_key(|record| record.duration);
+    if !fastest.is_empty() {
+        lines.push(String::new());
+        lines.push(i18n::message("stats-fastest"));
+        for (rank, record) in fastest.iter().take(FASTEST_SOLVES).enumerate() {
+            let solve = i18n::message_with("stats-fastest-solve",
+                                           &[&record.difficulty,
+                                             &record.attempts_used,
+                                             &format_date(record.timestamp)]);
+            lines.push(format!("{:>3}. {:>6}  {}",
+                               rank + 1,
+                               format_duration(record.duration),
+                               solve));
+        }
+    }
+
+    let mut summary = lines.join("\n");
+    summary.push('\n');
+    summary
+}
+
+/// Lines up rows of values under their headings, each column as wide as its widest cell.
+fn table(headings: &[String], rows: &[Vec<String>]) -> Vec<String> {
+    let widths = headings.iter()
+        .enumerate()
+        .map(|(column, heading)| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .fold(heading.chars().count(), cmp::max)
+        })
+        .collect::<Vec<_>>();
+    let line = |cells: &[String]| {
+        cells.iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:>width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+
+    let mut lines = vec![line(headings)];
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration as StdDuration;
+
+    use ::time::{Duration, PreciseTime};
+
+    use ::game::{GameEvent, GameSettings, Subscriber};
+    use super::{Stopwatch, StatsTracker};
+
+    #[test]
+    fn finished_game_lasts_as_long_as_it_was_played() {
+        let settings = GameSettings::new(5);
+        let mut tracker = StatsTracker::new(&settings);
+        let started = PreciseTime::now();
+
+        // Each frame spends most of its time drawing, after the lap has been taken.
+        let mut stopwatch = Stopwatch::start();
+        for _ in 0..5 {
+            tracker.update(stopwatch.lap());
+            thread::sleep(StdDuration::from_millis(20));
+        }
+        tracker.update(stopwatch.lap());
+        tracker.notify(&GameEvent::Won { bypassed: false });
+        let played = started.to(PreciseTime::now());
+
+        let duration = tracker.record().unwrap().duration;
+        assert!(duration >= Duration::milliseconds(100), "{} is too short", duration);
+        assert!(duration <= played, "{} is longer than {}", duration, played);
+    }
+
+    #[test]
+    fn time_after_the_end_is_not_counted() {
+        let settings = GameSettings::new(5);
+        let mut tracker = StatsTracker::new(&settings);
+        tracker.update(Duration::seconds(3));
+        tracker.notify(&GameEvent::LockedOut);
+        tracker.update(Duration::seconds(5));
+
+        assert_eq!(tracker.record().unwrap().duration, Duration::seconds(3));
+    }
+}
@@ -0,0 +1,781 @@
+//! Serves the game to terminals that connect over telnet. Each connection gets its own session,
+//! drawn with ANSI escape sequences written to the socket instead of through ncurses.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io;
+use std::io::prelude::*;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration as StdDuration;
+
+use ::ncurses::{KEY_BACKSPACE, KEY_DC, KEY_DOWN, KEY_END, KEY_F, KEY_HOME, KEY_IC, KEY_LEFT,
+                KEY_NPAGE, KEY_PPAGE, KEY_RIGHT, KEY_UP};
+use ::time::{Duration, PreciseTime};
+
+use ::ansi;
+use ::effects::{EffectSettings, Effects};
+use ::game::{GameEvent, GameState, InputEvent, Subscriber};
+use ::keys::KeyBindings;
+use ::markers::Markers;
+use ::menu::{self, Menu, MenuInput};
+use ::replay::ReplayControl;
+use ::screen::Screen;
+use ::theme::Theme;
+use ::window::{self, Page, Prompt, Window};
+
+/// The port terminals are served on unless another is given.
+pub const DEFAULT_PORT: u16 = 2323;
+
+/// The size of a client's terminal until it tells us otherwise.
+const DEFAULT_WIDTH: i32 = 80;
+const DEFAULT_HEIGHT: i32 = 24;
+
+/// How long to wait for a key before drawing the next frame.
+const INPUT_TIMEOUT_MS: u64 = 10;
+
+/// How long a client may take to accept a frame before it's taken to have gone.
+const WRITE_TIMEOUT_SECS: u64 = 10;
+
+/// How long an escape is held back in case the rest of an escape sequence follows it.
+const ESCAPE_TIMEOUT_MS: i64 = 100;
+
+// Telnet commands and options (RFC 854, 857, 858, 1073).
+const SE: u8 = 240;
+const SB: u8 = 250;
+const WILL: u8 = 251;
+const WONT: u8 = 252;
+const DO: u8 = 253;
+const DONT: u8 = 254;
+const IAC: u8 = 255;
+
+const BINARY: u8 = 0;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const NAWS: u8 = 31;
+
+/// Puts the client in character mode: we echo (by drawing), input isn't buffered into lines, text
+/// is UTF-8 rather than 7-bit ASCII, and the client reports the size of its window.
+const NEGOTIATION: [u8; 18] = [IAC, WILL, ECHO,
+                               IAC, WILL, SUPPRESS_GO_AHEAD,
+                               IAC, DO, SUPPRESS_GO_AHEAD,
+                               IAC, WILL, BINARY,
+                               IAC, DO, BINARY,
+                               IAC, DO, NAWS];
+
+/// Switch to the alternate screen and report the mouse, including movement, in xterm's SGR
+/// format, and switch back.
+const START_SCREEN: &'static str = "\x1b[?1049h\x1b[?1000h\x1b[?1003h\x1b[?1006h";
+const END_SCREEN: &'static str = "\x1b[?1006l\x1b[?1003l\x1b[?1000l\x1b[0m\x1b[?25h\x1b[?1049l";
+
+const KEY_ESC: i32 = 27;
+
+/// Subnegotiations longer than this are none we understand, so the rest is dropped.
+const MAX_SUBNEGOTIATION: usize = 16;
+
+/// Something a client sent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Keys typed, as the bytes the terminal sends for them.
+    Data(Vec<u8>),
+
+    /// The client's window changed size, to a width and height in characters.
+    Resize(i32, i32),
+
+    Closed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Data,
+
+    /// After a carriage return, which may be followed by a line feed or NUL that belongs to it.
+    Return,
+
+    /// After IAC.
+    Command,
+
+    /// After IAC and WILL, WONT, DO or DONT, waiting for the option.
+    Option,
+
+    /// Inside IAC SB ... IAC SE.
+    Subnegotiation,
+
+    /// After IAC inside a subnegotiation.
+    SubnegotiationCommand,
+}
+
+/// Separates what a client types from the telnet protocol around it.
+///
+/// Options the client agrees to or refuses need no answer, since we only ask for the ones we
+/// offer and go without any that are refused. Enter arrives as a carriage return followed by a
+/// line feed or NUL, or on its own, and is passed on as a line feed, as ncurses does.
+pub struct Decoder {
+    state: State,
+    subnegotiation: Vec<u8>,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder {
+            state: State::Data,
+            subnegotiation: vec![],
+        }
+    }
+
+    pub fn decode(&mut self, bytes: &[u8]) -> Vec<Event> {
+        let mut events = vec![];
+        let mut data = vec![];
+        for &byte in bytes {
+            if let Some((width, height)) = self.feed(byte, &mut data) {
+                if !data.is_empty() {
+                    events.push(Event::Data(data));
+                    data = vec![];
+                }
+                events.push(Event::Resize(width, height));
+            }
+        }
+        if !data.is_empty() {
+            events.push(Event::Data(data));
+        }
+        events
+    }
+
+    /// Reads one byte, adding it to `data` if it was typed. Returns the new window size if the
+    /// byte finished a report of it.
+    fn feed(&mut self, byte: u8, data: &mut Vec<u8>) -> Option<(i32, i32)> {
+        match self.state {
+            State::Data | State::Return => {
+                let after_return = self.state == State::Return;
+                self.state = State::Data;
+                match byte {
+                    IAC => self.state = State::Command,
+                    b'\n' | 0 if after_return => (),
+                    b'\r' => {
+                        data.push(b'\n');
+                        self.state = State::Return;
+                    }
+                    0 => (),
+                    byte => data.push(byte),
+                }
+            }
+            State::Command => {
+                self.state = match byte {
+                    IAC => {
+                        data.push(IAC);
+                        State::Data
+                    }
+                    SB => {
+                        self.subnegotiation.clear();
+                        State::Subnegotiation
+                    }
+                    WILL | WONT | DO | DONT => State::Option,
+                    _ => State::Data,
+                };
+            }
+            State::Option => self.state = State::Data,
+            State::Subnegotiation => {
+                if byte == IAC {
+                    self.state = State::SubnegotiationCommand;
+                } else if self.subnegotiation.len() < MAX_SUBNEGOTIATION {
+                    self.subnegotiation.push(byte);
+                }
+            }
+            State::SubnegotiationCommand => {
+                match byte {
+                    SE => {
+                        self.state = State::Data;
+                        return self.window_size();
+                    }
+                    IAC => {
+                        self.subnegotiation.push(IAC);
+                        self.state = State::Subnegotiation;
+                    }
+                    _ => self.state = State::Data,
+                }
+            }
+        }
+        None
+    }
+
+    /// The window size in a finished subnegotiation, if that's what it was.
+    fn window_size(&self) -> Option<(i32, i32)> {
+        let bytes = &self.subnegotiation;
+        if bytes.len() != 5 || bytes[0] != NAWS {
+            return None;
+        }
+        let width = (bytes[1] as i32) << 8 | bytes[2] as i32;
+        let height = (bytes[3] as i32) << 8 | bytes[4] as i32;
+        if width > 0 && height > 0 {
+            Some((width, height))
+        } else {
+            None
+        }
+    }
+}
+
+/// A key or mouse event read from a client.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Key {
+    /// A key, as the code ncurses would give it.
+    Code(i32),
+
+    /// A mouse report in xterm's SGR format: the button and modifiers, the position, and whether
+    /// it was a press (or movement) rather than a release.
+    Mouse {
+        button: i32,
+        x: i32,
+        y: i32,
+        pressed: bool,
+    },
+}
+
+/// Takes the next key from the bytes a client has sent. Returns `None` if there are no bytes,
+/// they end partway through an escape sequence and the rest is still to come, or they start with
+/// an escape sequence for a key the game has no use for.
+///
+/// An escape at the very end may be the start of a sequence that the network split, so it's only
+/// taken as the Esc key once `escape_waited` says nothing more came in time.
+fn read_key(bytes: &mut VecDeque<u8>, escape_waited: bool) -> Option<Key> {
+    let first = match bytes.front() {
+        Some(&byte) => byte,
+        None => return None,
+    };
+    if first != KEY_ESC as u8 {
+        bytes.pop_front();
+        return Some(Key::Code(match first {
+            0x08 | 0x7f => KEY_BACKSPACE,
+            byte => byte as i32,
+        }));
+    }
+
+    // An escape followed by anything but the start of a sequence is the Esc key.
+    let introducer = match bytes.get(1) {
+        Some(&byte) if byte == b'[' || byte == b'O' => byte,
+        None if !escape_waited => return None,
+        _ => {
+            bytes.pop_front();
+            return Some(Key::Code(KEY_ESC));
+        }
+    };
+
+    // Parameters run until a final byte between @ and ~.
+    let end = match bytes.iter().skip(2).position(|&byte| byte >= 0x40 && byte <= 0x7e) {
+        Some(end) => end + 2,
+        None => return None,
+    };
+    let sequence = bytes.drain(..end + 1).collect::<Vec<_>>();
+    let parameters = String::from_utf8_lossy(&sequence[2..end]).into_owned();
+    let last = sequence[end];
+
+    if introducer == b'[' && parameters.starts_with('<') && (last == b'M' || last == b'm') {
+        let numbers = parameters[1..]
+            .split(';')
+            .map(|number| number.parse::<i32>().unwrap_or(0))
+            .collect::<Vec<_>>();
+        if numbers.len() != 3 {
+            return None;
+        }
+        return Some(Key::Mouse {
+            button: numbers[0],
+            x: numbers[1] - 1,
+            y: numbers[2] - 1,
+            pressed: last == b'M',
+        });
+    }
+
+    let code = match (last, parameters.split(';').next().unwrap_or("")) {
+        (b'A', _) => KEY_UP,
+        (b'B', _) => KEY_DOWN,
+        (b'C', _) => KEY_RIGHT,
+        (b'D', _) => KEY_LEFT,
+        (b'H', _) => KEY_HOME,
+        (b'F', _) => KEY_END,
+        (b'P', _) => KEY_F(1),
+        (b'Q', _) => KEY_F(2),
+        (b'R', _) => KEY_F(3),
+        (b'S', _) => KEY_F(4),
+        (b'~', "1") | (b'~', "7") => KEY_HOME,
+        (b'~', "2") => KEY_IC,
+        (b'~', "3") => KEY_DC,
+        (b'~', "4") | (b'~', "8") => KEY_END,
+        (b'~', "5") => KEY_PPAGE,
+        (b'~', "6") => KEY_NPAGE,
+        (b'~', "15") => KEY_F(5),
+        (b'~', "17") => KEY_F(6),
+        (b'~', "18") => KEY_F(7),
+        (b'~', "19") => KEY_F(8),
+        (b'~', "20") => KEY_F(9),
+        (b'~', "21") => KEY_F(10),
+        (b'~', "23") => KEY_F(11),
+        (b'~', "24") => KEY_F(12),
+        _ => return None,
+    };
+    Some(Key::Code(code))
+}
+
+/// What has come in from a client but not been read yet.
+struct Input {
+    events: Receiver<Event>,
+    bytes: VecDeque<u8>,
+    width: i32,
+    height: i32,
+
+    /// Whether the client has gone, in which case every screen is left as soon as it's shown.
+    closed: bool,
+
+    /// When the escape at the end of `bytes` arrived, if that's where they end.
+    escape_since: Option<PreciseTime>,
+}
+
+impl Input {
+    /// Collects what the client has sent, waiting a moment for something if there are no keys
+    /// to read.
+    fn receive(&mut self, wait: bool) {
+        let waiting = self.bytes.is_empty() || self.escape_since.is_some();
+        if wait && waiting && !self.closed {
+            let timeout = StdDuration::from_millis(INPUT_TIMEOUT_MS);
+            match self.events.recv_timeout(timeout) {
+                Ok(event) => self.apply(event),
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => self.closed = true,
+            }
+        }
+        while let Ok(event) = self.events.try_recv() {
+            self.apply(event);
+        }
+    }
+
+    /// Takes the next key, holding back an escape at the end until the rest of a sequence has had
+    /// time to arrive.
+    fn read_key(&mut self) -> Option<Key> {
+        let lone_escape = self.bytes.len() == 1 && self.bytes[0] == KEY_ESC as u8;
+        if !lone_escape {
+            self.escape_since = None;
+            return read_key(&mut self.bytes, false);
+        }
+
+        let now = PreciseTime::now();
+        let since = *self.escape_since.get_or_insert(now);
+        let waited = self.closed || since.to(now) >= Duration::milliseconds(ESCAPE_TIMEOUT_MS);
+        if waited {
+            self.escape_since = None;
+        }
+        read_key(&mut self.bytes, waited)
+    }
+
+    fn apply(&mut self, event: Event) {
+        match event {
+            Event::Data(bytes) => self.bytes.extend(bytes),
+            Event::Resize(width, height) => {
+                self.width = width;
+                self.height = height;
+            }
+            Event::Closed => self.closed = true,
+        }
+    }
+}
+
+/// A window on a terminal at the other end of a telnet connection.
+pub struct TelnetWindow {
+    stream: TcpStream,
+    input: RefCell<Input>,
+
+    theme: Theme,
+    effects: Effects,
+    keys: KeyBindings,
+    markers: Markers,
+
+    /// Replaces the exit hint at the bottom of the screen, if set.
+    status: Option<String>,
+
+    /// Lines shown to the right of the terminal, if set.
+    panel: Option<Vec<String>>,
+
+    frame: Screen,
+
+    /// What the client's terminal shows, so that only what changed needs to be sent.
+    sent: Screen,
+}
+
+impl TelnetWindow {
+    pub fn new(stream: TcpStream,
+               theme: &Theme,
+               effects: EffectSettings,
+               keys: KeyBindings,
+               markers: Markers)
+               -> io::Result<TelnetWindow> {
+        stream.set_nodelay(true)?;
+        stream.set_write_timeout(Some(StdDuration::from_secs(WRITE_TIMEOUT_SECS)))?;
+        (&stream).write_all(&NEGOTIATION)?;
+        (&stream).write_all(START_SCREEN.as_bytes())?;
+
+        let (sender, receiver) = mpsc::channel();
+        read_events(stream.try_clone()?, sender);
+
+        Ok(TelnetWindow {
+            stream: stream,
+            input: RefCell::new(Input {
+                events: receiver,
+                bytes: VecDeque::new(),
+                width: DEFAULT_WIDTH,
+                height: DEFAULT_HEIGHT,
+                closed: false,
+                escape_since: None,
+            }),
+            theme: theme.clone(),
+            effects: Effects::new(effects),
+            keys: keys,
+            markers: markers,
+            status: None,
+            panel: None,
+            frame: Screen::new(0, 0),
+            sent: Screen::new(0, 0),
+        })
+    }
+
+    /// Writes to the client. If it can't be reached, it's taken to have gone.
+    fn send(&self, text: &str) {
+        if let Err(e) = (&self.stream).write_all(text.as_bytes()) {
+            let mut input = self.input.borrow_mut();
+            if !input.closed {
+                info!("Lost connection to telnet client: {}", e);
+                input.closed = true;
+            }
+        }
+    }
+
+    /// A blank screen the size of the client's window.
+    fn screen(&self) -> Screen {
+        let mut input = self.input.borrow_mut();
+        input.receive(false);
+        Screen::new(input.width, input.height)
+    }
+
+    fn closed(&self) -> bool {
+        self.input.borrow().closed
+    }
+
+    /// Reads the next key, waiting a moment for one if none has arrived.
+    fn next_key(&self) -> Option<Key> {
+        let mut input = self.input.borrow_mut();
+        input.receive(true);
+        input.read_key()
+    }
+
+    /// The next key, ignoring the mouse.
+    fn next_code(&self) -> Option<i32> {
+        match self.next_key() {
+            Some(Key::Code(code)) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Sends the parts of a screen that the client doesn't show yet.
+    fn display(&mut self, screen: &Screen) {
+        let output = ansi::render_changes(&self.sent, screen, &self.theme);
+        if !output.is_empty() {
+            self.send(&output);
+            self.sent = screen.clone();
+        }
+    }
+
+    /// Finishes a frame with the panel, the status line and effects, then shows it.
+    fn present(&mut self, mut screen: Screen) {
+        window::draw_overlays(&mut screen, &self.panel, &self.status);
+        self.effects.apply(&mut screen);
+        self.display(&screen);
+        self.frame = screen;
+    }
+
+    /// Turns a mouse report into the same events as the ncurses window's mouse handling.
+    fn mouse_input(button: i32, x: i32, y: i32, pressed: bool) -> Option<InputEvent> {
+        const MOTION: i32 = 32;
+        const WHEEL: i32 = 64;
+
+        if button & WHEEL != 0 {
+            Some(InputEvent::ScrollConsole(if button & 1 == 0 { 1 } else { -1 }))
+        } else if !pressed || button & MOTION != 0 {
+            Some(InputEvent::MoveTo(x, y))
+        } else {
+            match button & 3 {
+                0 => Some(InputEvent::SelectAt(x, y)),
+                2 => Some(InputEvent::InspectAt(x, y)),
+                _ => Some(InputEvent::MoveTo(x, y)),
+            }
+        }
+    }
+}
+
+impl Drop for TelnetWindow {
+    fn drop(&mut self) {
+        self.send(END_SCREEN);
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+impl Window for TelnetWindow {
+    fn handle_input(&self, game_state: &GameState) -> Option<InputEvent> {
+        if self.closed() {
+            return Some(InputEvent::Quit);
+        }
+
+        match self.next_key() {
+            Some(Key::Code(code)) if game_state.command_line.is_some() => {
+                window::command_input(code)
+            }
+            Some(Key::Code(code)) => self.keys.control(code).map(|control| control.input_event()),
+            Some(Key::Mouse { button, x, y, pressed }) => {
+                TelnetWindow::mouse_input(button, x, y, pressed)
+            }
+            None => None,
+        }
+    }
+
+    fn handle_replay_input(&self) -> Option<ReplayControl> {
+        if self.closed() {
+            return Some(ReplayControl::Quit);
+        }
+        self.next_code().and_then(window::replay_control)
+    }
+
+    fn set_status(&mut self, status: Option<String>) {
+        self.status = status;
+    }
+
+    fn set_panel(&mut self, panel: Option<Vec<String>>) {
+        self.panel = panel;
+    }
+
+    fn frame(&self) -> &Screen {
+        &self.frame
+    }
+
+    fn render_prompt(&mut self, prompt: &Prompt, message: &str) {
+        let mut screen = self.screen();
+        prompt.draw(&mut screen, message);
+        self.display(&screen);
+        self.frame = screen;
+    }
+
+    fn update(&mut self, elapsed: Duration) {
+        self.effects.update(elapsed);
+    }
+
+    fn notify(&mut self, event: &GameEvent) {
+        self.effects.notify(event);
+    }
+
+    fn render(&mut self, game_state: &GameState) {
+        let mut screen = self.screen();
+        window::draw(&mut screen, game_state, &self.markers);
+        self.present(screen);
+    }
+
+    fn render_menu(&mut self, menu: &Menu) {
+        let mut screen = self.screen();
+        menu::draw(&mut screen, menu);
+        self.present(screen);
+    }
+
+    fn handle_menu_input(&self) -> Option<MenuInput> {
+        if self.closed() {
+            return Some(MenuInput::Back);
+        }
+        self.next_code().and_then(|key| window::menu_input(&self.keys, key))
+    }
+
+    fn render_page(&mut self, page: &mut Page) {
+        let mut screen = self.screen();
+        page.draw(&mut screen);
+        self.display(&screen);
+        self.frame = screen;
+    }
+
+    fn read_key(&self) -> Option<i32> {
+        if self.closed() {
+            return Some(window::KEY_ESC);
+        }
+        self.next_code()
+    }
+
+    fn set_effects(&mut self, settings: EffectSettings) {
+        self.effects = Effects::new(settings);
+    }
+
+    fn bell(&mut self) {
+        self.send("\x07");
+    }
+}
+
+/// Reads what a client sends on its own thread, passing it on until the connection closes.
+fn read_events(mut stream: TcpStream, sender: Sender<Event>) {
+    thread::spawn(move || {
+        let mut decoder = Decoder::new();
+        let mut buffer = [0; 256];
+        loop {
+            let count = match stream.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(count) => count,
+            };
+            for event in decoder.decode(&buffer[..count]) {
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+        let _ = sender.send(Event::Closed);
+    });
+}
+
+/// Accepts telnet connections until the process is stopped, running `session` on its own thread
+/// for each one.
+pub fn serve<F>(port: u16, session: F) -> io::Result<()>
+    where F: Fn(TcpStream) + Send + Sync + 'static
+{
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    println!("Serving terminals on port {}.", port);
+
+    let session = Arc::new(session);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Could not accept telnet connection: {}", e);
+                continue;
+            }
+        };
+        let address = stream.peer_addr()
+            .map(|address| address.to_string())
+            .unwrap_or_else(|_| "an unknown address".to_string());
+        println!("Terminal connected from {}.", address);
+
+        let session = session.clone();
+        thread::spawn(move || {
+            session(stream);
+            println!("Terminal at {} disconnected.", address);
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use ::ncurses::{KEY_BACKSPACE, KEY_DC, KEY_F, KEY_UP};
+
+    use super::{read_key, Decoder, Event, Key, DO, IAC, KEY_ESC, NAWS, SB, SE, WILL};
+
+    fn bytes(text: &[u8]) -> VecDeque<u8> {
+        text.iter().cloned().collect()
+    }
+
+    fn keys(text: &[u8]) -> Vec<Key> {
+        let mut bytes = bytes(text);
+        let mut keys = vec![];
+        while let Some(key) = read_key(&mut bytes, true) {
+            keys.push(key);
+        }
+        keys
+    }
+
+    #[test]
+    fn decoder_passes_typed_bytes_through() {
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.decode(b"abc"), vec![Event::Data(b"abc".to_vec())]);
+        assert_eq!(decoder.decode(&[]), vec![]);
+    }
+
+    #[test]
+    fn decoder_reads_window_size() {
+        let mut decoder = Decoder::new();
+        let mut report = vec![b'a', IAC, SB, NAWS, 0, 120, 0, 40, IAC, SE, b'b'];
+        assert_eq!(decoder.decode(&report),
+                   vec![Event::Data(vec![b'a']), Event::Resize(120, 40), Event::Data(vec![b'b'])]);
+
+        // A size byte of 255 is doubled, and a report can arrive in pieces.
+        report = vec![IAC, SB, NAWS, 1, IAC, IAC, 0, 50, IAC, SE];
+        assert_eq!(decoder.decode(&report[..4]), vec![]);
+        assert_eq!(decoder.decode(&report[4..]), vec![Event::Resize(511, 50)]);
+    }
+
+    #[test]
+    fn decoder_ignores_negotiation() {
+        let mut decoder = Decoder::new();
+        let bytes = [IAC, WILL, NAWS, b'x', IAC, DO, 3, IAC, 241, b'y'];
+        assert_eq!(decoder.decode(&bytes), vec![Event::Data(b"xy".to_vec())]);
+    }
+
+    #[test]
+    fn decoder_unescapes_iac() {
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.decode(&[IAC, IAC]), vec![Event::Data(vec![IAC])]);
+    }
+
+    #[test]
+    fn decoder_turns_returns_into_line_feeds() {
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.decode(b"a\r\nb\r\0c\rd"),
+                   vec![Event::Data(b"a\nb\nc\nd".to_vec())]);
+
+        // The line feed after a return may come in the next packet.
+        assert_eq!(decoder.decode(b"\r"), vec![Event::Data(b"\n".to_vec())]);
+        assert_eq!(decoder.decode(b"\n"), vec![]);
+    }
+
+    #[test]
+    fn read_key_reads_plain_keys() {
+        assert_eq!(keys(b"a\x7f\x08\n"),
+                   vec![Key::Code('a' as i32),
+                        Key::Code(KEY_BACKSPACE),
+                        Key::Code(KEY_BACKSPACE),
+                        Key::Code('\n' as i32)]);
+    }
+
+    #[test]
+    fn read_key_reads_escape_sequences() {
+        assert_eq!(keys(b"\x1b[A\x1bOP\x1b[3~\x1b[1;5A"),
+                   vec![Key::Code(KEY_UP),
+                        Key::Code(KEY_F(1)),
+                        Key::Code(KEY_DC),
+                        Key::Code(KEY_UP)]);
+        assert_eq!(keys(b"\x1b[<0;10;5M\x1b[<2;1;1m"),
+                   vec![Key::Mouse { button: 0, x: 9, y: 4, pressed: true },
+                        Key::Mouse { button: 2, x: 0, y: 0, pressed: false }]);
+    }
+
+    #[test]
+    fn read_key_skips_unknown_sequences() {
+        let mut bytes = bytes(b"\x1b[99~a");
+        assert_eq!(read_key(&mut bytes, true), None);
+        assert_eq!(read_key(&mut bytes, true), Some(Key::Code('a' as i32)));
+    }
+
+    #[test]
+    fn read_key_reads_escape() {
+        assert_eq!(keys(b"\x1b\x1bx"),
+                   vec![Key::Code(KEY_ESC), Key::Code(KEY_ESC), Key::Code('x' as i32)]);
+    }
+
+    #[test]
+    fn read_key_waits_for_split_sequences() {
+        let mut bytes = bytes(b"\x1b");
+        assert_eq!(read_key(&mut bytes, false), None);
+        bytes.push_back(b'[');
+        assert_eq!(read_key(&mut bytes, false), None);
+        bytes.push_back(b'A');
+        assert_eq!(read_key(&mut bytes, false), Some(Key::Code(KEY_UP)));
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn read_key_takes_lone_escape_after_waiting() {
+        let mut bytes = bytes(b"\x1b");
+        assert_eq!(read_key(&mut bytes, false), None);
+        assert_eq!(read_key(&mut bytes, true), Some(Key::Code(KEY_ESC)));
+        assert!(bytes.is_empty());
+    }
+}
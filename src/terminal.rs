@@ -0,0 +1,216 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use ::rustc_serialize::json::{Json, Object};
+
+use ::consts::{MAX_DIFFICULTY, MIN_DIFFICULTY};
+use ::i18n;
+use ::game::GameSettings;
+use ::skill::LockLevel;
+use ::words;
+
+/// A custom terminal: the passwords that guard it and what's inside once it's hacked.
+///
+/// Terminals are written as JSON:
+///
+/// ```json
+/// {
+///     "header": ["VAULT-TEC SECURITY TERMINAL"],
+///     "lock_level": "average",
+///     "passwords": ["secret", "vaults", "atomic"],
+///     "menu": [
+///         { "label": "Read log", "page": ["Day 1.", "Still no water chip."] },
+///         { "label": "Unlock door", "set": ["door_open"], "message": "Door unlocked." },
+///         { "label": "Lock door", "requires": "door_open", "clear": ["door_open"] },
+///         { "label": "Security", "menu": [] }
+///     ]
+/// }
+/// ```
+pub struct TerminalScript {
+    /// Lines shown at the top of every menu once the terminal has been hacked.
+    pub header: Vec<String>,
+    pub lock_level: LockLevel,
+    pub passwords: Vec<String>,
+    pub menu: Vec<MenuEntry>,
+}
+
+pub struct MenuEntry {
+    pub label: String,
+
+    /// A flag that must be set for the entry to be shown.
+    pub requires: Option<String>,
+
+    pub content: Content,
+}
+
+/// What choosing a menu entry does.
+pub enum Content {
+    Page(Vec<String>),
+    Menu(Vec<MenuEntry>),
+
+    /// Sets and clears flags, then shows a message if there is one.
+    Action {
+        set: Vec<String>,
+        clear: Vec<String>,
+        message: Vec<String>,
+    },
+}
+
+/// The flags set by a terminal's actions.
+pub type Flags = BTreeSet<String>;
+
+impl TerminalScript {
+    pub fn load(path: &Path) -> Result<TerminalScript, String> {
+        let mut contents = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let json = Json::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+        TerminalScript::from_json(&json).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn from_json(json: &Json) -> Result<TerminalScript, String> {
+        let object = json.as_object().ok_or("expected an object")?;
+
+        let header = match object.get("header") {
+            Some(header) => string_list(header, "header")?,
+            None => vec![i18n::message("header")],
+        };
+
+        let lock_level = match object.get("lock_level") {
+            Some(level) => {
+                let name = level.as_string().ok_or("lock_level should be a string")?;
+                LockLevel::parse(name)
+                    .ok_or_else(|| format!("unknown lock level '{}'", name))?
+            }
+            None => LockLevel::Average,
+        };
+
+        let passwords = match object.get("passwords") {
+            Some(passwords) => string_list(passwords, "passwords")?,
+            None => return Err("missing passwords".to_string()),
+        };
+        let passwords = check_passwords(&passwords)?;
+
+        let menu = match object.get("menu") {
+            Some(menu) => menu_entries(menu)?,
+            None => vec![],
+        };
+
+        Ok(TerminalScript {
+            header: header,
+            lock_level: lock_level,
+            passwords: passwords,
+            menu: menu,
+        })
+    }
+
+    /// The settings for hacking the terminal, using its passwords instead of the dictionary.
+    pub fn settings(&self) -> GameSettings {
+        let length = words::length(&self.passwords[0]) as i32;
+        GameSettings { word_pool: Some(self.passwords.clone()), ..GameSettings::new(length) }
+    }
+}
+
+impl MenuEntry {
+    pub fn visible(&self, flags: &Flags) -> bool {
+        self.requires.as_ref().map_or(true, |flag| flags.contains(flag))
+    }
+}
+
+/// Checks that the passwords can be played against each other, putting them in the form they're
+/// shown on the board.
+fn check_passwords(passwords: &[String]) -> Result<Vec<String>, String> {
+    if passwords.len() < 2 {
+        return Err("there must be at least two passwords".to_string());
+    }
+
+    let mut normalized = vec![];
+    for password in passwords {
+        let word = words::normalize(password)
+            .ok_or_else(|| format!("password '{}' should only contain letters", password))?;
+        normalized.push(word);
+    }
+
+    let length = words::length(&normalized[0]) as i32;
+    if length < MIN_DIFFICULTY || length > MAX_DIFFICULTY {
+        return Err(format!("passwords must be between {} and {} letters long",
+                           MIN_DIFFICULTY,
+                           MAX_DIFFICULTY));
+    }
+
+    for (password, word) in passwords.iter().zip(&normalized) {
+        if words::length(word) as i32 != length {
+            return Err(format!("password '{}' should be {} letters long, like the first",
+                               password,
+                               length));
+        }
+    }
+    Ok(normalized)
+}
+
+/// Reads a string or a list of strings.
+fn string_list(json: &Json, name: &str) -> Result<Vec<String>, String> {
+    match *json {
+        Json::String(ref string) => Ok(vec![string.clone()]),
+        Json::Array(ref array) => {
+            array.iter()
+                .map(|item| {
+                    item.as_string()
+                        .map(String::from)
+                        .ok_or_else(|| format!("{} should only contain strings", name))
+                })
+                .collect()
+        }
+        _ => Err(format!("{} should be a string or a list of strings", name)),
+    }
+}
+
+fn menu_entries(json: &Json) -> Result<Vec<MenuEntry>, String> {
+    let array = json.as_array().ok_or("menu should be a list")?;
+    array.iter().map(menu_entry).collect()
+}
+
+fn menu_entry(json: &Json) -> Result<MenuEntry, String> {
+    let object = json.as_object().ok_or("menu entries should be objects")?;
+    let label = object.get("label")
+        .and_then(|label| label.as_string())
+        .ok_or("menu entry is missing a label")?
+        .to_string();
+
+    let requires = match object.get("requires") {
+        Some(flag) => {
+            Some(flag.as_string()
+                .ok_or_else(|| format!("'{}': requires should be a flag name", label))?
+                .to_string())
+        }
+        None => None,
+    };
+
+    let content = if let Some(page) = object.get("page") {
+        Content::Page(string_list(page, "page")?)
+    } else if let Some(menu) = object.get("menu") {
+        Content::Menu(menu_entries(menu)?)
+    } else if object.contains_key("set") || object.contains_key("clear") {
+        Content::Action {
+            set: optional_list(object, "set")?,
+            clear: optional_list(object, "clear")?,
+            message: optional_list(object, "message")?,
+        }
+    } else {
+        return Err(format!("'{}' needs a page, a menu, or flags to set or clear", label));
+    };
+
+    Ok(MenuEntry {
+        label: label,
+        requires: requires,
+        content: content,
+    })
+}
+
+fn optional_list(object: &Object, name: &str) -> Result<Vec<String>, String> {
+    object.get(name).map_or(Ok(vec![]), |json| string_list(json, name))
+}
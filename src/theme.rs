@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use ::keyfile;
+use ::ncurses::*;
+
+/// A foreground and background color, as understood by ncurses.
+#[derive(Debug, Clone, Copy)]
+pub struct ColorPair {
+    pub foreground: i16,
+    pub background: i16,
+}
+
+/// The colors used to draw the terminal.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub normal: ColorPair,
+    pub highlight: ColorPair,
+}
+
+/// The names of the themes that are always available.
+pub const BUILTIN_THEMES: [&'static str; 4] = ["green", "amber", "white", "blue"];
+
+impl Theme {
+    fn builtin(name: &str) -> Option<Theme> {
+        let (foreground, background) = match name {
+            // Pip-Boy green.
+            "green" => (COLOR_GREEN, COLOR_BLACK),
+            // New Vegas amber. Most terminals render yellow as something close enough.
+            "amber" => (COLOR_YELLOW, COLOR_BLACK),
+            "white" => (COLOR_WHITE, COLOR_BLACK),
+            // Vault-Tec blue and gold.
+            "blue" => (COLOR_YELLOW, COLOR_BLUE),
+            _ => return None,
+        };
+
+        Some(Theme {
+            name: name.to_string(),
+            normal: ColorPair {
+                foreground: foreground,
+                background: background,
+            },
+            highlight: ColorPair {
+                foreground: background,
+                background: foreground,
+            },
+        })
+    }
+
+    /// Looks up a theme by name. If there is no built-in theme with that name, the name is treated
+    /// as the path to a theme file.
+    pub fn load(name: &str) -> Result<Theme, String> {
+        if let Some(theme) = Theme::builtin(name) {
+            return Ok(theme);
+        }
+
+        let path = Path::new(name);
+        if !path.is_file() {
+            return Err(format!("unknown theme '{}' (expected one of {} or a theme file)",
+                               name,
+                               BUILTIN_THEMES.join(", ")));
+        }
+
+        Theme::from_file(path)
+    }
+
+    /// Reads a theme file. A theme file contains `key = value` lines, where the keys are
+    /// `foreground`, `background`, `highlight-foreground` and `highlight-background` and the
+    /// values are color names or ncurses color numbers. Blank lines and lines starting with `#`
+    /// are ignored. Missing highlight colors default to the inverse of the normal colors.
+    pub fn from_file(path: &Path) -> Result<Theme, String> {
+        let mut normal = ColorPair {
+            foreground: COLOR_WHITE,
+            background: COLOR_BLACK,
+        };
+        let mut highlight_foreground = None;
+        let mut highlight_background = None;
+
+        keyfile::read(path, "key = value", |_, key, value| {
+            let color = parse_color(value).ok_or_else(|| format!("unknown color '{}'", value))?;
+            match key {
+                "foreground" => normal.foreground = color,
+                "background" => normal.background = color,
+                "highlight-foreground" => highlight_foreground = Some(color),
+                "highlight-background" => highlight_background = Some(color),
+                _ => return Err(format!("unknown key '{}'", key)),
+            }
+            Ok(())
+        })?;
+
+        let name = path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+
+        Ok(Theme {
+            name: name,
+            normal: normal,
+            highlight: ColorPair {
+                foreground: highlight_foreground.unwrap_or(normal.background),
+                background: highlight_background.unwrap_or(normal.foreground),
+            },
+        })
+    }
+}
+
+fn parse_color(value: &str) -> Option<i16> {
+    let color = match &value.to_lowercase()[..] {
+        "black" => COLOR_BLACK,
+        "red" => COLOR_RED,
+        "green" => COLOR_GREEN,
+        "yellow" => COLOR_YELLOW,
+        "blue" => COLOR_BLUE,
+        "magenta" => COLOR_MAGENTA,
+        "cyan" => COLOR_CYAN,
+        "white" => COLOR_WHITE,
+        number => {
+            match number.parse() {
+                Ok(n) if n >= 0 && n < 256 => n,
+                _ => return None,
+            }
+        }
+    };
+    Some(color)
+}
+
+/// Approximates an ncurses color number as an HTML color, using the xterm palette.
+pub fn color_rgb(color: i16) -> String {
+    const BASIC: [(u8, u8, u8); 16] = [(0, 0, 0),
+                                       (205, 0, 0),
+                                       (0, 205, 0),
+                                       (205, 205, 0),
+                                       (0, 0, 238),
+                                       (205, 0, 205),
+                                       (0, 205, 205),
+                                       (229, 229, 229),
+                                       (127, 127, 127),
+                                       (255, 0, 0),
+                                       (0, 255, 0),
+                                       (255, 255, 0),
+                                       (92, 92, 255),
+                                       (255, 0, 255),
+                                       (0, 255, 255),
+                                       (255, 255, 255)];
+    const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    let color = if color < 0 { 0 } else { color as usize };
+    let (r, g, b) = if color < 16 {
+        BASIC[color]
+    } else if color < 232 {
+        let index = color - 16;
+        (CUBE_LEVELS[index / 36], CUBE_LEVELS[(index / 6) % 6], CUBE_LEVELS[index % 6])
+    } else {
+        let level = (8 + 10 * (color.min(255) - 232)) as u8;
+        (level, level, level)
+    };
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
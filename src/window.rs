@@ -1,35 +1,123 @@
 use ::game::*;
 use ::ncurses::*;
 
-use std::ascii::AsciiExt;
-use std::char;
-use std::env;
+use std::cmp;
+use std::ffi::{CStr, CString};
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
 use std::mem;
+use std::str;
 
 use std::iter::repeat;
 use ::itertools::Itertools;
 
-use ::consts::*;
+use ::time::Duration;
 
-pub struct NCursesWindow;
+use ::consts::*;
+use ::effects::{EffectSettings, Effects};
+use ::i18n;
+use ::keys::{Control, KeyBindings};
+use ::markers::{HighlightStyle, Markers};
+use ::menu::{self, Menu, MenuInput};
+use ::replay::ReplayControl;
+use ::screen::{Screen, Style};
+use ::theme::Theme;
+use ::words;
+
+/// The color pair used for normal text.
+const NORMAL_PAIR: i16 = 1;
+
+/// The color pair used for the highlighted entity under the cursor.
+const HIGHLIGHT_PAIR: i16 = 2;
+
+/// Turn xterm's reporting of mouse movement on and off. ncurses only asks for button presses
+/// unless the terminal's description says otherwise.
+const ENABLE_MOUSE_MOTION: &'static str = "\x1b[?1003h";
+const DISABLE_MOUSE_MOTION: &'static str = "\x1b[?1003l";
+
+/// The key code of the escape key, which also stands for a window closing under a prompt or page.
+pub const KEY_ESC: i32 = 27;
+const KEY_ENTER: i32 = '\n' as i32;
+const KEY_DELETE: i32 = 127;
+
+pub struct NCursesWindow {
+    /// The attribute used to highlight the entity under the cursor.
+    highlight: attr_t,
+
+    effects: Effects,
+    keys: KeyBindings,
+    markers: Markers,
+
+    /// Whether the terminal was asked to report mouse movement, and so needs to be told to stop.
+    mouse_motion: bool,
+
+    /// Replaces the exit hint at the bottom of the screen, if set.
+    status: Option<String>,
+
+    /// Lines shown to the right of the terminal, if set.
+    panel: Option<Vec<String>>,
+
+    frame: Screen,
+}
 
-pub fn create() -> Box<Window> {
-    Box::new(NCursesWindow::new())
+pub fn create(theme: &Theme,
+              effects: EffectSettings,
+              keys: KeyBindings,
+              markers: Markers)
+              -> Box<Window> {
+    Box::new(NCursesWindow::new(theme, effects, keys, markers))
 }
 
 pub trait Window {
-    fn render(&self, &GameState);
-    fn handle_input(&self, &mut GameState) -> Option<InputEvent>;
+    fn render(&mut self, &GameState);
+    fn handle_input(&self, &GameState) -> Option<InputEvent>;
+
+    /// Advances any animations by the time elapsed since the last frame.
+    fn update(&mut self, _: Duration) {}
+
+    /// Lets the window react to something that happened in the game.
+    fn notify(&mut self, _: &GameEvent) {}
+
+    /// Reads the controls for watching a replay instead of playing.
+    fn handle_replay_input(&self) -> Option<ReplayControl>;
+
+    /// Replaces the hint at the bottom of the screen.
+    fn set_status(&mut self, Option<String>);
+
+    /// Shows lines of text to the right of the terminal, such as a race's scoreboard, or hides
+    /// them.
+    fn set_panel(&mut self, Option<Vec<String>>);
+
+    /// The most recently rendered frame.
+    fn frame(&self) -> &Screen;
+
+    /// Shows a line of text being typed, such as the player's name.
+    fn render_prompt(&mut self, &Prompt, message: &str);
+
+    fn render_menu(&mut self, &Menu);
+    fn handle_menu_input(&self) -> Option<MenuInput>;
+
+    /// Shows a page of text, such as statistics.
+    fn render_page(&mut self, &mut Page);
+
+    /// Reads a key for a prompt or page, waiting a moment for one. Reads escape if the window
+    /// has closed.
+    fn read_key(&self) -> Option<i32>;
+
+    /// Turns CRT effects on or off.
+    fn set_effects(&mut self, EffectSettings);
+
+    /// Rings the terminal's bell.
+    fn bell(&mut self);
 }
 
 impl NCursesWindow {
-    fn new() -> NCursesWindow {
-        // Enable all mouse events for the current terminal.
-        env::set_var("TERM", "xterm-1003");
-
+    fn new(theme: &Theme,
+           effects: EffectSettings,
+           keys: KeyBindings,
+           markers: Markers)
+           -> NCursesWindow {
         setlocale(LcCategory::all, "");
         initscr();
         raw();
@@ -40,266 +128,594 @@ impl NCursesWindow {
         noecho();
 
         let mouse_events = ALL_MOUSE_EVENTS | REPORT_MOUSE_POSITION;
-        mousemask(mouse_events as u32, None);
+        let mouse_supported = mousemask(mouse_events as u32, None) != 0;
         mouseinterval(0);
 
-        if has_mouse() {
-            info!("Mouse driver initialized.")
+        // Terminals that speak xterm's mouse protocol can report movement too, which lets the
+        // cursor follow the mouse.
+        let mouse_motion = mouse_supported && NCursesWindow::has_xterm_mouse();
+        if mouse_motion {
+            putp(ENABLE_MOUSE_MOTION);
+        }
+
+        if !mouse_supported {
+            info!("Terminal does not support the mouse.");
+        } else if mouse_motion {
+            info!("Mouse driver initialized with movement reporting.");
+        } else {
+            info!("Mouse driver initialized without movement reporting.");
+        }
+
+        let colors = NCursesWindow::init_colors(theme);
+        if colors {
+            info!("Using color theme '{}'.", theme.name);
+        } else {
+            info!("Terminal does not support colors, falling back to monochrome.");
+        }
+
+        let highlight = match markers.highlight {
+            HighlightStyle::Reverse if colors => COLOR_PAIR(HIGHLIGHT_PAIR),
+            HighlightStyle::Reverse => A_STANDOUT(),
+            HighlightStyle::Underline => A_UNDERLINE(),
+            HighlightStyle::Bold => A_BOLD(),
+        };
+
+        NCursesWindow {
+            highlight: highlight,
+            effects: Effects::new(effects),
+            keys: keys,
+            markers: markers,
+            mouse_motion: mouse_motion,
+            status: None,
+            panel: None,
+            frame: Screen::new(0, 0),
+        }
+    }
+
+    /// Whether the terminal's mouse key is the start of an xterm mouse report.
+    fn has_xterm_mouse() -> bool {
+        let name = CString::new("kmous").unwrap();
+        let value = unsafe { ll::tigetstr(name.as_ptr()) };
+
+        // tigetstr returns null if the terminal doesn't have the capability, and -1 if it isn't a
+        // string capability.
+        if value.is_null() || value as isize == -1 {
+            return false;
+        }
+        let value = unsafe { CStr::from_ptr(value) }.to_string_lossy();
+        value.starts_with("\x1b[M") || value.starts_with("\x1b[<")
+    }
+
+    /// Reads a mouse event. Clicking selects, right-clicking compares a word with the guesses so
+    /// far, the wheel scrolls the console, and anything else just moves the cursor.
+    fn handle_mouse_input() -> Option<InputEvent> {
+        let mut event: MEVENT = unsafe { mem::uninitialized() };
+        if getmouse(&mut event) != OK {
+            return None;
+        }
+
+        let happened = |mask: i32| event.bstate & (mask as u32) != 0;
+        if happened(BUTTON1_PRESSED | BUTTON1_CLICKED) {
+            Some(InputEvent::SelectAt(event.x, event.y))
+        } else if happened(BUTTON3_PRESSED | BUTTON3_CLICKED) {
+            Some(InputEvent::InspectAt(event.x, event.y))
+        } else if happened(BUTTON4_PRESSED) {
+            Some(InputEvent::ScrollConsole(1))
+        } else if happened(BUTTON5_PRESSED) {
+            Some(InputEvent::ScrollConsole(-1))
         } else {
-            info!("Error initializing mouse driver.");
+            Some(InputEvent::MoveTo(event.x, event.y))
+        }
+    }
+
+    /// Copies a screen buffer to the terminal.
+    fn display(&self, screen: &Screen) {
+        erase();
+
+        for (row, cells) in screen.rows().enumerate() {
+            mv(row as i32, 0);
+
+            // Write runs of characters with the same style all at once.
+            for (style, run) in &cells.iter().group_by(|cell| cell.style) {
+                let text = run.map(|cell| cell.ch).collect::<String>();
+                let attributes = self.attributes(style);
+                attron(attributes);
+                addstr(&text);
+                attroff(attributes);
+            }
+        }
+
+        match screen.cursor {
+            Some((x, y)) => {
+                curs_set(CURSOR_VISIBILITY::CURSOR_VISIBLE);
+                mv(y, x);
+            }
+            None => {
+                curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+            }
         }
 
-        NCursesWindow
+        refresh();
+    }
+
+    /// Finishes a frame with the panel, the status line and effects, then shows it.
+    fn present(&mut self, mut screen: Screen) {
+        draw_overlays(&mut screen, &self.panel, &self.status);
+        self.effects.apply(&mut screen);
+        self.display(&screen);
+        self.frame = screen;
+    }
+
+    fn attributes(&self, style: Style) -> attr_t {
+        let mut attributes = 0;
+        if style.highlight {
+            attributes |= self.highlight;
+        }
+        if style.dim {
+            attributes |= A_DIM();
+        }
+        attributes
+    }
+
+    /// Sets up the color pairs for the theme. Returns false if the terminal can't display them.
+    fn init_colors(theme: &Theme) -> bool {
+        if !has_colors() || start_color() == ERR {
+            return false;
+        }
+
+        let normal = theme.normal;
+        let highlight = theme.highlight;
+        if init_pair(NORMAL_PAIR, normal.foreground, normal.background) == ERR ||
+           init_pair(HIGHLIGHT_PAIR, highlight.foreground, highlight.background) == ERR {
+            return false;
+        }
+
+        bkgd(' ' as chtype | COLOR_PAIR(NORMAL_PAIR));
+        true
     }
 }
 
 impl Drop for NCursesWindow {
     fn drop(&mut self) {
+        if self.mouse_motion {
+            putp(DISABLE_MOUSE_MOTION);
+        }
         refresh();
         endwin();
     }
 }
 
 impl Window for NCursesWindow {
-    fn handle_input(&self, game_state: &mut GameState) -> Option<InputEvent> {
+    fn handle_input(&self, game_state: &GameState) -> Option<InputEvent> {
         let ch: i32 = getch();
 
-        // Allow WASD and HJKL controls
-        const KEY_W: i32 = 'w' as i32;
-        const KEY_A: i32 = 'a' as i32;
-        const KEY_S: i32 = 's' as i32;
-        const KEY_D: i32 = 'd' as i32;
-
-        const KEY_H: i32 = 'h' as i32;
-        const KEY_J: i32 = 'j' as i32;
-        const KEY_K: i32 = 'k' as i32;
-        const KEY_L: i32 = 'l' as i32;
+        if game_state.command_line.is_some() {
+            return command_input(ch);
+        }
 
-        const KEY_ESC: i32 = 27;
-        const KEY_ENTER: i32 = '\n' as i32;
         match ch as i32 {
-            KEY_LEFT | KEY_A | KEY_H => Some(InputEvent::Left),
-            KEY_RIGHT | KEY_D | KEY_L => Some(InputEvent::Right),
-            KEY_UP | KEY_W | KEY_K => Some(InputEvent::Up),
-            KEY_DOWN | KEY_S | KEY_J => Some(InputEvent::Down),
-            KEY_MOUSE => {
-                let mut event: MEVENT = unsafe { mem::uninitialized() };
-                assert!(getmouse(&mut event) == OK);
-
-                game_state.cursor_position = (event.x, event.y);
-                if event.bstate & (BUTTON1_PRESSED as u32) != 0 {
-                    Some(InputEvent::Action)
-                } else {
-                    None
-                }
+            KEY_MOUSE => NCursesWindow::handle_mouse_input(),
+            key => self.keys.control(key).map(|control| control.input_event()),
+        }
+    }
+
+    fn handle_replay_input(&self) -> Option<ReplayControl> {
+        replay_control(getch())
+    }
+
+    fn set_status(&mut self, status: Option<String>) {
+        self.status = status;
+    }
+
+    fn set_panel(&mut self, panel: Option<Vec<String>>) {
+        self.panel = panel;
+    }
+
+    fn frame(&self) -> &Screen {
+        &self.frame
+    }
+
+    fn render_prompt(&mut self, prompt: &Prompt, message: &str) {
+        let mut screen = Screen::new(COLS(), LINES());
+        prompt.draw(&mut screen, message);
+        self.display(&screen);
+        self.frame = screen;
+    }
+
+    fn update(&mut self, elapsed: Duration) {
+        self.effects.update(elapsed);
+    }
+
+    fn notify(&mut self, event: &GameEvent) {
+        self.effects.notify(event);
+    }
+
+    fn render(&mut self, game_state: &GameState) {
+        let mut screen = Screen::new(COLS(), LINES());
+        draw(&mut screen, game_state, &self.markers);
+        self.present(screen);
+    }
+
+    fn render_menu(&mut self, menu: &Menu) {
+        let mut screen = Screen::new(COLS(), LINES());
+        menu::draw(&mut screen, menu);
+        self.present(screen);
+    }
+
+    fn handle_menu_input(&self) -> Option<MenuInput> {
+        menu_input(&self.keys, getch())
+    }
+
+    fn render_page(&mut self, page: &mut Page) {
+        let mut screen = Screen::new(COLS(), LINES());
+        page.draw(&mut screen);
+        self.display(&screen);
+        self.frame = screen;
+    }
+
+    fn read_key(&self) -> Option<i32> {
+        match getch() {
+            ERR => {
+                napms(10);
+                None
             }
-            KEY_ENTER => Some(InputEvent::Action),
-            KEY_ESC => Some(InputEvent::Quit),
-            _ => None,
+            key => Some(key),
         }
     }
 
-    fn render(&self, game_state: &GameState) {
-        refresh();
-        erase();
+    fn set_effects(&mut self, settings: EffectSettings) {
+        self.effects = Effects::new(settings);
+    }
 
-        let starting_line = MARGIN + 5;
-
-        // If the game is over, render the ending state and return early.
-        if let Some(ref ending) = game_state.status {
-            match *ending {
-                GameEnding::Won => {
-                    curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
-                    let reader = BufReader::new(File::open("resources/vault_boy.txt").unwrap());
-                    let mut line_counter = 0;
-                    for line in reader.lines().map(|l| l.unwrap()) {
-                        mvprintw(line_counter as i32,
-                                 0,
-                                 &format!("{:^1$}", line, WINDOW_WIDTH as usize));
-                        line_counter += 1;
+    fn bell(&mut self) {
+        beep();
+    }
+}
+
+/// Adds the panel and the status line to a frame.
+pub fn draw_overlays(screen: &mut Screen, panel: &Option<Vec<String>>, status: &Option<String>) {
+    if let Some(ref panel) = *panel {
+        for (i, line) in panel.iter().enumerate() {
+            screen.print(MARGIN + i as i32, WINDOW_WIDTH + MARGIN, line);
+        }
+    }
+    if let Some(ref status) = *status {
+        let bottom = screen.height() - 1;
+        let width = screen.width() as usize;
+        screen.print(bottom, 0, &format!("{:1$}", status, width));
+    }
+}
+
+/// Reads a key while a command is being typed at the console.
+pub fn command_input(key: i32) -> Option<InputEvent> {
+    match key {
+        KEY_ENTER => Some(InputEvent::Action),
+        KEY_ESC => Some(InputEvent::Quit),
+        KEY_DELETE | KEY_BACKSPACE => Some(InputEvent::Erase),
+        key if key >= 0x20 && key < 0x7f => Some(InputEvent::Type(key as u8 as char)),
+        _ => None,
+    }
+}
+
+/// Reads a key while a replay is being watched.
+pub fn replay_control(key: i32) -> Option<ReplayControl> {
+    const KEY_SPACE: i32 = ' ' as i32;
+    const KEY_PERIOD: i32 = '.' as i32;
+    const KEY_PLUS: i32 = '+' as i32;
+    const KEY_EQUALS: i32 = '=' as i32;
+    const KEY_MINUS: i32 = '-' as i32;
+    const KEY_P: i32 = 'p' as i32;
+
+    match key {
+        KEY_SPACE => Some(ReplayControl::TogglePause),
+        KEY_PERIOD | KEY_RIGHT => Some(ReplayControl::Step),
+        KEY_PLUS | KEY_EQUALS | KEY_UP => Some(ReplayControl::Faster),
+        KEY_MINUS | KEY_DOWN => Some(ReplayControl::Slower),
+        KEY_P => Some(ReplayControl::Screenshot),
+        KEY_ESC => Some(ReplayControl::Quit),
+        _ => None,
+    }
+}
+
+/// Reads a key while a menu is shown.
+pub fn menu_input(keys: &KeyBindings, key: i32) -> Option<MenuInput> {
+    match keys.control(key) {
+        Some(Control::Up) => Some(MenuInput::Up),
+        Some(Control::Down) => Some(MenuInput::Down),
+        Some(Control::Select) => Some(MenuInput::Select),
+        Some(Control::Quit) => Some(MenuInput::Back),
+        _ => None,
+    }
+}
+
+/// A line of text being typed in answer to a prompt.
+pub struct Prompt {
+    input: String,
+    max_length: usize,
+
+    /// Bytes of a multibyte character that haven't all arrived yet.
+    pending: Vec<u8>,
+}
+
+impl Prompt {
+    pub fn new(max_length: usize) -> Prompt {
+        Prompt {
+            input: String::new(),
+            max_length: max_length,
+            pending: vec![],
+        }
+    }
+
+    pub fn draw(&self, screen: &mut Screen, message: &str) {
+        let row = (MARGIN + 5 + ROWS) / 2;
+        screen.print(row, MARGIN, message);
+        screen.print(row + 2, MARGIN, &format!(">{}", self.input));
+        screen.cursor = Some((MARGIN + 1 + self.input.chars().count() as i32, row + 2));
+    }
+
+    /// Reads a key. Returns the answer once the player is done: the text they typed, or `None` if
+    /// they cancelled.
+    pub fn handle(&mut self, key: i32) -> Option<Option<String>> {
+        match key {
+            KEY_ESC => return Some(None),
+            KEY_ENTER => return Some(Some(self.input.clone())),
+            KEY_DELETE | KEY_BACKSPACE => {
+                self.input.pop();
+            }
+            byte if byte >= 0x20 && byte < 0x100 => {
+                self.pending.push(byte as u8);
+                if let Ok(text) = str::from_utf8(&self.pending) {
+                    if self.input.chars().count() < self.max_length {
+                        self.input.push_str(text);
                     }
-                    mvprintw(line_counter as i32,
-                             0,
-                             &format!("{:^1$}", "ACCESS GRANTED", WINDOW_WIDTH as usize));
                 }
-                GameEnding::Lost => {
-                    curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
-                    mvprintw((starting_line + ROWS) / 2,
-                             0,
-                             &format!("{:^1$}", "TERMINAL LOCKED", WINDOW_WIDTH as usize));
-                    mvprintw((starting_line + ROWS + 1) / 2,
-                             0,
-                             &format!("{:^1$}",
-                                      "PLEASE CONTACT AN ADMINISTRATOR",
-                                      WINDOW_WIDTH as usize));
+                if str::from_utf8(&self.pending).is_ok() || self.pending.len() >= 4 {
+                    self.pending.clear();
                 }
             }
-            return;
+            _ => (),
         }
+        None
+    }
+}
 
-        // Print information at top
-        mvprintw(MARGIN, MARGIN, "ROBCO INDUSTRIES (TM) TERMLINK PROTOCOL");
-        mvprintw(MARGIN + 1, MARGIN, "ENTER PASSWORD NOW");
-        mvprintw(LINES() - 1, 0, "Press Esc to exit");
+/// A page of text being read.
+pub struct Page<'a> {
+    title: &'a str,
+    lines: &'a [String],
+    offset: usize,
 
-        // Print attempts remaining
-        let visual_attempts = repeat("█")
-            .take(game_state.attempts as usize)
-            .join(" ");
-        mvprintw(MARGIN + 3,
-                 MARGIN,
-                 &format!("{} ATTEMPT(S) LEFT: {}",
-                          game_state.attempts,
-                          visual_attempts));
+    /// How many lines fit on the screen last time it was drawn.
+    visible: usize,
+}
 
-        // Draw random addresses and word columns
-        let highlight_positions = match game_state.get_entity_at_cursor() {
-            Some(cursor_entity) => {
-                if cursor_entity.highlighted() {
-                    let (start, end) = cursor_entity.indices();
-                    let start_x = start % WORD_COLUMN_WIDTH as usize;
-                    let start_y = start / WORD_COLUMN_WIDTH as usize;
-                    let end_x = end % WORD_COLUMN_WIDTH as usize;
-                    let end_y = end / WORD_COLUMN_WIDTH as usize;
-                    Some(((start_x, start_y), (end_x, end_y)))
-                } else {
-                    None
-                }
-            }
-            None => None,
-        };
+impl<'a> Page<'a> {
+    pub fn new(title: &'a str, lines: &'a [String]) -> Page<'a> {
+        Page {
+            title: title,
+            lines: lines,
+            offset: 0,
+            visible: 0,
+        }
+    }
 
-        // Draw both columns
-        for (column_index, column) in game_state.columns.iter().enumerate() {
-            let word_data: Vec<char> = column.render_word_data().chars().collect::<Vec<char>>();
-            let word_chunks = word_data.chunks(WORD_COLUMN_WIDTH as usize);
-            for (line, (address, word_chunk)) in column.addresses
-                .iter()
-                .zip(word_chunks.into_iter())
-                .enumerate() {
-                let row = starting_line + line as i32;
-                let col = MARGIN + column_index as i32 * (COLUMN_WIDTH + COLUMN_PADDING);
-                let hex_address: String =
-                    format!("{:#01$X}", address, ADDRESS_COLUMN_WIDTH as usize);
-                let word_row: String = word_chunk.iter().map(|&c| c).collect::<String>();
-
-                mvprintw(row, col, &(hex_address + " "));
-
-                if let Some(((start_x, start_y), (end_x, end_y))) = highlight_positions {
-                    if game_state.get_cursor_column_index().unwrap() != column_index {
-                        // We're not in the correct column, so just write out the line and
-                        // continue.
-                        addstr(&word_row);
-                        continue;
-                    }
+    pub fn draw(&mut self, screen: &mut Screen) {
+        let first_row = MARGIN + 3;
+        screen.print(MARGIN, MARGIN, &i18n::message("header"));
+        screen.print(MARGIN + 1, MARGIN, &self.title.to_uppercase());
 
-                    // If the highlight ends on the same line, we just iterate over the chunk and
-                    // turn on and off the highlight at the start and the end.
-                    if start_y == line && start_y == end_y {
-                        for (i, c) in word_row.chars().enumerate() {
-                            if i == start_x {
-                                attron(A_STANDOUT());
-                            }
-
-                            if i == end_x {
-                                attroff(A_STANDOUT());
-                            }
-                            addch(c as u32);
-                        }
-                    } else if start_y == line {
-                        for (i, c) in word_row.chars().enumerate() {
-
-                            if i == start_x {
-                                attron(A_STANDOUT());
-                            }
-                            addch(c as u32);
-                        }
-                        attroff(A_STANDOUT());
-                    } else if end_y == line {
-                        attron(A_STANDOUT());
-                        for (i, c) in word_row.chars().enumerate() {
-
-                            if i == end_x {
-                                attroff(A_STANDOUT());
-                            }
-                            addch(c as u32);
-                        }
-                    } else {
-                        addstr(&word_row);
-                    }
-                } else {
-                    addstr(&word_row);
-                }
-            }
+        // Leave room for the hint at the bottom.
+        self.visible = cmp::max(0, screen.height() - 1 - first_row) as usize;
+        for (i, line) in self.lines.iter().skip(self.offset).take(self.visible).enumerate() {
+            screen.print(first_row + i as i32, MARGIN, line);
         }
 
-        // Draw the console.
-        let console_entry = if let Some(entity) = game_state.get_entity_at_cursor() {
-            match *entity {
-                CursorEntity::Word { ref word, .. } => word.to_ascii_uppercase(),
-                CursorEntity::Brackets { ref pair, .. } => pair.0.to_string(),
+        let bottom = screen.height() - 1;
+        screen.print(bottom, 0, &i18n::message("hint-page"));
+    }
+
+    /// Reads a key, returning false once the player leaves the page.
+    pub fn handle(&mut self, key: i32) -> bool {
+        const KEY_Q: i32 = 'q' as i32;
+
+        let (lines, visible) = (self.lines.len(), self.visible);
+        match key {
+            KEY_UP if self.offset > 0 => self.offset -= 1,
+            KEY_DOWN if self.offset + visible < lines => self.offset += 1,
+            KEY_PPAGE => self.offset = self.offset.saturating_sub(visible),
+            KEY_NPAGE => {
+                self.offset = cmp::min(self.offset + visible, lines.saturating_sub(visible))
             }
-        } else {
+            KEY_ESC | KEY_ENTER | KEY_Q => return false,
+            _ => (),
+        }
+        true
+    }
+}
 
-            // If we're in a column, display the character at the cursor. Otherwise, display an empty
-            // string.
-            match game_state.get_cursor_column_index() {
-                Some(..) => {
-                    let (x, y) = game_state.cursor_position;
-                    char::from_u32(mvinch(y, x) as u32).unwrap().to_string()
+/// Draws the game into a screen buffer.
+pub fn draw(screen: &mut Screen, game_state: &GameState, markers: &Markers) {
+    let starting_line = MARGIN + 5;
+
+    // If the game is over, render the ending state and return early.
+    if let Some(ref ending) = game_state.status {
+        match *ending {
+            GameEnding::Won => {
+                let reader = BufReader::new(File::open("resources/vault_boy.txt").unwrap());
+                let mut line_counter = 0;
+                for line in reader.lines().map(|l| l.unwrap()) {
+                    screen.print(line_counter as i32,
+                                 0,
+                                 &format!("{:^1$}", line, WINDOW_WIDTH as usize));
+                    line_counter += 1;
                 }
-                None => "".to_string(),
+                screen.print(line_counter as i32,
+                             0,
+                             &format!("{:^1$}",
+                                      i18n::message("access-granted"),
+                                      WINDOW_WIDTH as usize));
             }
-        };
+            GameEnding::Lost => {
+                screen.print((starting_line + ROWS) / 2,
+                             0,
+                             &format!("{:^1$}",
+                                      i18n::message("terminal-locked"),
+                                      WINDOW_WIDTH as usize));
+                screen.print((starting_line + ROWS + 1) / 2,
+                             0,
+                             &format!("{:^1$}",
+                                      i18n::message("contact-administrator"),
+                                      WINDOW_WIDTH as usize));
+            }
+        }
+        let bottom = screen.height() - 1;
+        screen.print(bottom, 0, &i18n::message("hint-game-over"));
+        return;
+    }
+
+    // Print information at top
+    screen.print(MARGIN, MARGIN, &i18n::message("header"));
+    screen.print(MARGIN + 1, MARGIN, &i18n::message("enter-password"));
+    let bottom = screen.height() - 1;
+    screen.print(bottom, 0, &i18n::message("hint-game"));
+
+    // Print attempts remaining
+    let visual_attempts = repeat("█")
+        .take(game_state.attempts as usize)
+        .join(" ");
+    screen.print(MARGIN + 3,
+                 MARGIN,
+                 &format!("{} {}",
+                          i18n::message_with("attempts-left", &[&game_state.attempts]),
+                          visual_attempts));
 
-        mvprintw(starting_line + ROWS - 1,
-                 MARGIN + 2 * COLUMN_WIDTH + COLUMN_PADDING + MARGIN,
-                 &format!(">{}", console_entry));
+    // Print the countdown in timed games, rounding up so that it only reads 0:00 at the end.
+    if let Some(time_left) = game_state.time_left {
+        let seconds = (time_left.num_milliseconds() + 999) / 1000;
+        screen.print(MARGIN + 3,
+                     MARGIN + 2 * COLUMN_WIDTH + COLUMN_PADDING + MARGIN,
+                     &i18n::message_with("time-left",
+                                         &[&format!("{}:{:02}", seconds / 60, seconds % 60)]));
+    }
 
-        // Draw the console entries, starting from the bottom.
-        let mut entries_row = starting_line + ROWS - 3;
-        for entry in game_state.entries.iter().rev() {
-            let col = MARGIN + 2 * COLUMN_WIDTH + COLUMN_PADDING + MARGIN;
+    // Draw both columns
+    for (column_index, column) in game_state.columns.iter().enumerate() {
+        let word_data: Vec<char> = column.render_word_data().chars().collect::<Vec<char>>();
+        let word_chunks = word_data.chunks(WORD_COLUMN_WIDTH as usize);
+        for (line, (address, word_chunk)) in column.addresses
+            .iter()
+            .zip(word_chunks.into_iter())
+            .enumerate() {
+            let row = starting_line + line as i32;
+            let col = MARGIN + column_index as i32 * (COLUMN_WIDTH + COLUMN_PADDING);
+            let hex_address: String = format!("{:#01$X}", address, ADDRESS_COLUMN_WIDTH as usize);
+            let word_row: String = word_chunk.iter().map(|&c| c).collect::<String>();
+
+            screen.print(row, col, &(hex_address + " " + &word_row));
+        }
+    }
 
-            // Only prints the lines if the entry would be within the address columns.
-            let mvprintw_checked = |row, col, lines: &[&str]| {
-                for (i, line) in lines.iter().rev().enumerate() {
-                    if row >= starting_line {
-                        mvprintw(row - i as i32, col, line);
-                    }
+    // Mark the words that are out of play, starting guessed words with their likeness if asked.
+    for (column_index, column) in game_state.columns.iter().enumerate() {
+        for entity in column.entities() {
+            let (word, index, marker, likeness) = match *entity {
+                CursorEntity::Word { ref word, index, guessed: true, .. } => {
+                    let likeness = match game_state.likeness_of(word) {
+                        Some(likeness) if markers.inline_likeness => likeness.to_string(),
+                        _ => String::new(),
+                    };
+                    (word, index, markers.guessed, likeness)
+                }
+                CursorEntity::Word { ref word, index, removed: true, .. } => {
+                    (word, index, markers.removed, String::new())
                 }
+                _ => continue,
             };
 
-            match *entry {
-                Entry::Incorrect { num_correct, ref word } => {
-                    mvprintw_checked(entries_row,
-                                     col,
-                                     &[&format!(">{}", word.to_ascii_uppercase()),
-                                       ">Entry denied",
-                                       &format!(">{}/{} correct.", num_correct, 7)]);
-                }
-                Entry::Correct { ref word } => {
-                    mvprintw_checked(entries_row,
-                                     col,
-                                     &[&format!(">{}", word.to_ascii_uppercase()),
-                                       ">Exact match!",
-                                       ">Please wait",
-                                       ">while system",
-                                       ">is accessed."]);
-                }
-                Entry::DudRemoval => {
-                    mvprintw_checked(entries_row, col, &[">", ">Dud removed."]);
-                }
-                Entry::AllowanceReplenish => {
-                    mvprintw_checked(entries_row, col, &[">", ">Allowance", ">replenished."]);
+            let marks = likeness.chars().chain(repeat(marker)).take(word.chars().count());
+            for (i, ch) in marks.enumerate() {
+                let (x, y) = screen_position(column_index, index + i);
+                screen.put(y, x, ch);
+            }
+        }
+    }
+
+    // Highlight the entity under the cursor.
+    if let Some(cursor_entity) = game_state.get_entity_at_cursor() {
+        if cursor_entity.highlighted() {
+            let column_index = game_state.get_cursor_column_index().unwrap();
+            let col = MARGIN + column_index as i32 * (COLUMN_WIDTH + COLUMN_PADDING) +
+                      ADDRESS_COLUMN_WIDTH + INNER_COLUMN_PADDING;
+            let (start, end) = cursor_entity.indices();
+            for index in start..end {
+                let row = starting_line + (index / WORD_COLUMN_WIDTH as usize) as i32;
+                let x = col + (index % WORD_COLUMN_WIDTH as usize) as i32;
+                if let Some(cell) = screen.cell_mut(row, x) {
+                    cell.style.highlight = true;
                 }
             }
+        }
+    }
 
-            entries_row -= entry.display_rows() as i32;
+    // Draw the console. While a command is being typed, it replaces whatever is under the cursor,
+    // scrolling so that the end of it is always visible.
+    let console_row = starting_line + ROWS - 1;
+    let console_col = MARGIN + 2 * COLUMN_WIDTH + COLUMN_PADDING + MARGIN;
+    let console_entry = if let Some(ref command) = game_state.command_line {
+        let chars = command.chars().collect::<Vec<_>>();
+        let visible = cmp::min(chars.len(), CONSOLE_WIDTH as usize - 1);
+        chars[chars.len() - visible..].iter().cloned().collect()
+    } else if let Some(entity) = game_state.get_entity_at_cursor() {
+        match *entity {
+            CursorEntity::Word { ref word, .. } => words::to_upper(word),
+            CursorEntity::Brackets { ref pair, .. } => pair.0.to_string(),
         }
+    } else {
+
+        // If we're in a column, display the character at the cursor. Otherwise, display an empty
+        // string.
+        match game_state.get_cursor_column_index() {
+            Some(..) => {
+                let (x, y) = game_state.cursor_position;
+                screen.get(y, x).map_or(String::new(), |cell| cell.ch.to_string())
+            }
+            None => "".to_string(),
+        }
+    };
+
+    screen.print(console_row, console_col, &format!(">{}", console_entry));
+
+    // Draw the console entries, starting from the bottom, or further back if the history has
+    // been scrolled.
+    let last_entry_row = starting_line + CONSOLE_ROWS - 1;
+    let mut entries_row = last_entry_row + game_state.console_scroll as i32;
+    for entry in game_state.entries.iter().rev() {
+        let col = MARGIN + 2 * COLUMN_WIDTH + COLUMN_PADDING + MARGIN;
+
+        // Only prints the lines that would be within the address columns, above the prompt.
+        let lines = entry.console_lines();
+        for (i, line) in lines.iter().rev().enumerate() {
+            let line_row = entries_row - i as i32;
+            if line_row >= starting_line && line_row <= last_entry_row {
+                screen.print(line_row, col, line);
+            }
+        }
+
+        entries_row -= lines.len() as i32;
+    }
 
-        // Move the cursor to the current position
-        let (x, y) = game_state.cursor_position;
-        mv(y, x);
+    // Show which way there's more history to scroll to, just right of the console.
+    let indicator_col = console_col + CONSOLE_WIDTH;
+    if game_state.console_scroll < game_state.max_console_scroll() {
+        screen.print(starting_line, indicator_col, "▲");
     }
+    if game_state.console_scroll > 0 {
+        screen.print(last_entry_row, indicator_col, "▼");
+    }
+
+    // Move the cursor to the current position, or to the end of the command being typed.
+    screen.cursor = match game_state.command_line {
+        Some(..) => Some((console_col + 1 + console_entry.chars().count() as i32, console_row)),
+        None => Some(game_state.cursor_position),
+    };
 }
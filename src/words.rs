@@ -0,0 +1,147 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::io::prelude::*;
+use std::path::Path;
+
+use ::unicode_normalization::UnicodeNormalization;
+use ::unicode_segmentation::UnicodeSegmentation;
+use ::unicode_width::UnicodeWidthChar;
+
+/// Where passwords come from if no other dictionary is given.
+pub const SYSTEM_DICTIONARY: &'static str = "/usr/share/dict/words";
+
+/// Prepares a word for the board, or returns `None` if it can't be played.
+///
+/// Every letter of a word takes up one cell of the board, so the word is composed into one
+/// character per letter, and words with letters that don't fit in a single cell (letters that
+/// only exist as a base and a combining mark, or double-width letters) are rejected, as are words
+/// containing anything but letters. Words are stored in lowercase.
+pub fn normalize(word: &str) -> Option<String> {
+    let word = word.trim().nfc().collect::<String>();
+    if word.is_empty() || word.graphemes(true).count() != word.chars().count() {
+        return None;
+    }
+    if !word.chars().all(|c| c.is_alphabetic() && c.width() == Some(1)) {
+        return None;
+    }
+    Some(word.chars().map(|c| single(c, c.to_lowercase())).collect())
+}
+
+/// How many letters long a word is.
+pub fn length(word: &str) -> usize {
+    word.graphemes(true).count()
+}
+
+/// A word in capitals, as the terminal shows it. Letters whose capital is more than one letter
+/// stay as they are so the word keeps its length, except for ß, which becomes ẞ.
+pub fn to_upper(word: &str) -> String {
+    word.chars()
+        .map(|c| {
+            match c {
+                'ß' => 'ẞ',
+                _ => single(c, c.to_uppercase()),
+            }
+        })
+        .collect()
+}
+
+/// How many letters two words have in the same places.
+pub fn likeness(a: &str, b: &str) -> usize {
+    a.graphemes(true).zip(b.graphemes(true)).filter(|&(x, y)| x == y).count()
+}
+
+/// The mapped character, if the mapping gives exactly one.
+fn single<I: Iterator<Item = char>>(c: char, mut mapped: I) -> char {
+    match (mapped.next(), mapped.next()) {
+        (Some(mapped), None) => mapped,
+        _ => c,
+    }
+}
+
+/// Reads the words of a given length from a dictionary with one word per line.
+///
+/// The system dictionary lists proper nouns with a capital, so they're left out. Other
+/// dictionaries are taken to be lists of words meant for the game, and may use any case.
+pub fn load(path: &Path, length: usize) -> io::Result<Vec<String>> {
+    let skip_capitalized = path == Path::new(SYSTEM_DICTIONARY);
+    let mut seen = BTreeSet::new();
+    let mut words = vec![];
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if skip_capitalized && line.chars().next().map_or(false, char::is_uppercase) {
+            continue;
+        }
+        if let Some(word) = normalize(&line) {
+            // Lists in any case can have the same word twice once it's in lowercase.
+            if self::length(&word) == length && seen.insert(word.clone()) {
+                words.push(word);
+            }
+        }
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    use super::{length, likeness, load, normalize, to_upper};
+
+    #[test]
+    fn words_are_composed_and_lowercased() {
+        assert_eq!(normalize("  Lemon\n"), Some("lemon".to_string()));
+        // u followed by a combining diaeresis is one letter once composed.
+        assert_eq!(normalize("U\u{308}BER"), Some("über".to_string()));
+        assert_eq!(length(&normalize("u\u{308}ber").unwrap()), 4);
+        assert_eq!(normalize("Straße"), Some("straße".to_string()));
+    }
+
+    #[test]
+    fn words_that_dont_fit_the_board_are_rejected() {
+        // x with an acute accent has no composed form.
+        assert_eq!(normalize("x\u{301}yz"), None);
+        // Double-width letters take up two cells.
+        assert_eq!(normalize("日本"), None);
+        assert_eq!(normalize("don't"), None);
+        assert_eq!(normalize("r2d2"), None);
+        assert_eq!(normalize("   "), None);
+    }
+
+    #[test]
+    fn capitals_keep_the_length_of_the_word() {
+        assert_eq!(to_upper("straße"), "STRAẞE");
+        assert_eq!(to_upper("über"), "ÜBER");
+        // The capital of ŉ is two letters, so it stays as it is.
+        assert_eq!(to_upper("ŉa"), "ŉA");
+    }
+
+    #[test]
+    fn likeness_compares_whole_letters() {
+        assert_eq!(likeness("lemon", "melon"), 3);
+        assert_eq!(likeness("über", "ober"), 3);
+        // A letter with a combining mark only matches the same letter with the same mark.
+        assert_eq!(likeness("x\u{301}ab", "x\u{301}cb"), 2);
+        assert_eq!(likeness("x\u{301}ab", "xcab"), 0);
+        assert_eq!(likeness("日本語", "日月語"), 2);
+    }
+
+    #[test]
+    fn dictionaries_keep_playable_words_of_the_length() {
+        let dir = env::temp_dir().join(format!("robco-term-words-{}", ::std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("words");
+        let mut file = File::create(&path).unwrap();
+        for line in &["Lemon", "melon", "LEMON", "can't", "mango", "x\u{301}abcd", "apple pie",
+                      "kiwi", "u\u{308}bers"] {
+            writeln!(file, "{}", line).unwrap();
+        }
+        drop(file);
+
+        let words = load(&path, 5).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(words, vec!["lemon", "melon", "mango", "übers"]);
+    }
+}